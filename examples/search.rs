@@ -41,19 +41,16 @@ fn main() -> eframe::Result<()> {
 }
 
 struct Application {
-	swr: swr::SWR<Fetcher, swr::runtime::Tokio>,
-	query_slot: Option<swr::Persisted<String, Fetcher, swr::runtime::Tokio>>,
+	results: swr::Debounced<String, Fetcher, swr::runtime::Tokio>,
 	search_query: String
 }
 
 impl Application {
 	pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
 		let swr = swr::new_in(Fetcher, swr::runtime::Tokio, swr::hook::Egui::new(&cc.egui_ctx));
-		Self {
-			swr,
-			query_slot: None,
-			search_query: String::new()
-		}
+		// only search once the user stops typing for 250ms
+		let results = swr.debounced(Duration::from_millis(250), swr::Options::immutable());
+		Self { results, search_query: String::new() }
 	}
 }
 
@@ -62,25 +59,17 @@ impl eframe::App for Application {
 		CentralPanel::default().show(ctx, |ui| {
 			ui.add_space(30.0);
 			ui.vertical_centered(|ui| {
-				if ui.add(egui::TextEdit::singleline(&mut self.search_query)).changed() {
-					if !self.search_query.is_empty() {
-						let old_data = self.query_slot.take().and_then(|c| c.get_shallow().data);
-						self.query_slot = Some(self.swr.persisted(&self.search_query, swr::Options {
-							fallback: old_data,
-							..swr::Options::immutable()
-						}));
-					} else {
-						self.query_slot.take();
-					}
+				if ui.add(egui::TextEdit::singleline(&mut self.search_query)).changed() && !self.search_query.is_empty() {
+					self.results.set_key(&self.search_query);
 				}
 
-				if let Some(slot) = self.query_slot.as_ref() {
-					let result = slot.get();
-					if result.loading {
-						ui.spinner();
+				if !self.search_query.is_empty() {
+					// keeps showing the previous query's results until the new query loads
+					let result = self.results.get();
+					if let Some(data) = result.data.as_deref() {
+						ui.add_enabled(!result.is_previous_data(), egui::Label::new(egui::RichText::new(data).heading()));
 					} else {
-						let data = result.data.as_deref().unwrap();
-						ui.heading(data);
+						ui.spinner();
 					}
 				} else {
 					ui.label("Start typing to search");
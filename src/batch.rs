@@ -0,0 +1,181 @@
+use std::{
+	any::Any,
+	future::{Future, poll_fn},
+	mem,
+	pin::{Pin, pin},
+	sync::Arc,
+	task::{Poll, Waker},
+	time::Duration
+};
+
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+
+use crate::{
+	SWRInner,
+	fetcher::{BatchFetcher, Fetcher},
+	runtime::Runtime,
+	util::{Oneshot, TaskSlot, TaskStartMode}
+};
+
+type FetchResult<T, F> = Result<<F as Fetcher>::Response<T>, <F as Fetcher>::Error>;
+type BatchFuture<T, F> = Pin<Box<dyn Future<Output = Vec<FetchResult<T, F>>> + Send>>;
+type Dispatch<T, F, R> = fn(Arc<SWRInner<F, R>>, Vec<<F as Fetcher>::Key>) -> BatchFuture<T, F>;
+type PendingFetch<T, F> = (<F as Fetcher>::Key, Arc<Oneshot<FetchResult<T, F>>>);
+
+/// Type-erased handle to a [`Batch`], so batches of all types can be stored together & flushed at the end of a frame.
+pub(crate) trait AnyBatch: Send + Sync {
+	/// Dispatches the pending batch, if there is one.
+	fn flush(&self);
+
+	fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+/// Collects fetches of keys with response type `T` so they can be dispatched together via
+/// [`BatchFetcher::fetch_many`].
+pub(crate) struct Batch<T: Send + Sync + 'static, F: Fetcher, R: Runtime> {
+	window: Duration,
+	dispatch: Dispatch<T, F, R>,
+	state: Mutex<BatchState<T, F, R>>
+}
+
+struct BatchState<T: Send + Sync + 'static, F: Fetcher, R: Runtime> {
+	pending: Vec<PendingFetch<T, F>>,
+	flush: bool,
+	dispatcher: Option<Waker>,
+	dispatcher_task: TaskSlot<R>
+}
+
+impl<T, F, R> Batch<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	pub fn new(window: Duration, runtime: R) -> Self
+	where
+		F: BatchFetcher
+	{
+		Self {
+			window,
+			dispatch: |inner, keys| Box::pin(async move { inner.fetcher.fetch_many::<T>(&keys).await }),
+			state: Mutex::new(BatchState {
+				pending: Vec::new(),
+				flush: false,
+				dispatcher: None,
+				dispatcher_task: TaskSlot::new(runtime)
+			})
+		}
+	}
+
+	/// Adds `key` to the pending batch. Its result will be sent through `result` once the batch is dispatched.
+	pub fn enqueue(self: &Arc<Self>, inner: &Arc<SWRInner<F, R>>, key: F::Key, result: Arc<Oneshot<FetchResult<T, F>>>) {
+		let mut state = self.state.lock();
+		state.pending.push((key, result));
+		if state.pending.len() == 1 {
+			// The dispatcher runs in its own task so that it isn't cancelled if the fetch that started the batch is
+			// aborted. The previous batch's dispatcher may still be waiting on `fetch_many`, so keep it running.
+			state
+				.dispatcher_task
+				.insert(TaskStartMode::Override, Arc::clone(self).dispatch(Arc::clone(inner)));
+		}
+	}
+
+	/// Removes the fetch with the given `result` channel from the pending batch, if it hasn't been dispatched yet.
+	fn cancel(&self, result: &Arc<Oneshot<FetchResult<T, F>>>) {
+		self.state.lock().pending.retain(|(_, pending)| !Arc::ptr_eq(pending, result));
+	}
+
+	async fn dispatch(self: Arc<Self>, inner: Arc<SWRInner<F, R>>) {
+		{
+			// Wait until the end of the frame, or for the window to elapse if fetches are being launched outside of
+			// rendering.
+			let mut window = pin!(inner.runtime.wait(self.window));
+			poll_fn(|cx| {
+				let mut state = self.state.lock();
+				if state.flush {
+					return Poll::Ready(());
+				}
+				state.dispatcher = Some(cx.waker().clone());
+				drop(state);
+				window.as_mut().poll(cx)
+			})
+			.await;
+		}
+
+		let pending = {
+			let mut state = self.state.lock();
+			state.flush = false;
+			state.dispatcher = None;
+			mem::take(&mut state.pending)
+		};
+		// every fetch in the batch was aborted before it could be dispatched
+		if pending.is_empty() {
+			return;
+		}
+		let (keys, senders): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+
+		#[cfg(feature = "tracing")]
+		{
+			tracing::debug!(keys = ?keys, "dispatching batch of {} keys", keys.len());
+		}
+
+		let mut results = (self.dispatch)(Arc::clone(&inner), keys).await.into_iter();
+		for sender in senders {
+			sender.send(results.next());
+		}
+	}
+}
+
+/// Removes a fetch from its batch when dropped, so that keys whose fetch is aborted aren't dispatched.
+pub(crate) struct PendingGuard<T: DeserializeOwned + Send + Sync + 'static, F: Fetcher, R: Runtime> {
+	batch: Arc<Batch<T, F, R>>,
+	result: Arc<Oneshot<FetchResult<T, F>>>
+}
+
+impl<T, F, R> PendingGuard<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	pub fn new(batch: Arc<Batch<T, F, R>>, result: Arc<Oneshot<FetchResult<T, F>>>) -> Self {
+		Self { batch, result }
+	}
+
+	pub async fn recv(&self) -> Option<FetchResult<T, F>> {
+		self.result.recv().await
+	}
+}
+
+impl<T, F, R> Drop for PendingGuard<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	fn drop(&mut self) {
+		self.batch.cancel(&self.result);
+	}
+}
+
+impl<T, F, R> AnyBatch for Batch<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	fn flush(&self) {
+		let mut state = self.state.lock();
+		if !state.pending.is_empty() {
+			state.flush = true;
+			if let Some(waker) = state.dispatcher.take() {
+				waker.wake();
+			}
+		}
+	}
+
+	fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+		self
+	}
+}
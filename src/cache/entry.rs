@@ -1,5 +1,6 @@
 use std::{
 	any::{Any, TypeId},
+	collections::VecDeque,
 	mem::MaybeUninit,
 	sync::{
 		Arc,
@@ -11,8 +12,11 @@ use std::{
 use parking_lot::RwLock;
 
 use crate::{
+	SWRInner,
+	cache::CacheSlot,
 	error::MismatchedTypeError,
-	fetcher::Fetcher,
+	fetcher::{Fetcher, Tag, Validators},
+	mutation::MutationState,
 	options::StoredOptions,
 	revalidate::RevalidateIntent,
 	runtime::Runtime,
@@ -53,10 +57,27 @@ impl CacheEntryStatus {
 	}
 }
 
+/// Starts a subscription for an entry; monomorphized for the entry's data type by `SWR::subscription`.
+pub type SubscribeFn<F, R> = fn(&mut CacheEntry<F, R>, &Arc<SWRInner<F, R>>, CacheSlot);
+
+/// Re-applies local changes on top of fresh data from the fetcher; set by `MutationQueue` while the entry has pending
+/// commands. Returns `None` to keep the data as-is.
+pub type RebaseFn = Arc<dyn Fn(&Arc<dyn Any + Send + Sync>) -> Option<Arc<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// Data replaced by mutations of an entry, used by `SWR::undo` & `SWR::redo`.
+#[derive(Default)]
+struct History {
+	// oldest first, bounded by `Options::history_depth`
+	undo: VecDeque<CacheEntryData>,
+	redo: Vec<CacheEntryData>
+}
+
 pub struct CacheEntry<F: Fetcher, R: Runtime> {
 	key: F::Key,
 
 	pub(crate) retry_count: AtomicU8,
+	// the delay before the last scheduled retry, used by `RetryPolicy::DecorrelatedJitter`
+	pub(crate) last_retry_delay_ms: AtomicU64,
 	status: CacheEntryStatus,
 	revalidate_intent: RevalidateIntent,
 	data: MaybeUninit<CacheEntryData>,
@@ -67,10 +88,40 @@ pub struct CacheEntry<F: Fetcher, R: Runtime> {
 	last_draw_time_offset: AtomicU64,
 	// offset from base time in nanos where u64::MAX is None, i.e. no request has been made
 	last_request_time_offset: AtomicU64,
+	// offset from base time in nanos where u64::MAX is None, i.e. no fetch has succeeded. unlike
+	// `last_request_time_offset`, this is not affected by mutations
+	last_fetch_time_offset: AtomicU64,
+	// offsets from base time in nanos where u64::MAX is None, i.e. the entry has never had data/an error
+	data_updated_time_offset: AtomicU64,
+	error_updated_time_offset: AtomicU64,
+
+	// number of completed fetches, successful or not
+	pub fetch_count: u32,
+	pub last_fetch_duration: Option<Duration>,
+	// the `RevalidateIntent` flags of the fetch in progress, or 0 if there is none
+	pub fetch_intent: u8,
+	// the version of the fetch whose intent is `fetch_intent`
+	fetch_version: u64,
+	// when the retry task is scheduled to retry; only meaningful while the retry task is running
+	pub retry_at: Option<Instant>,
 
 	pub fetch_task: TaskSlot<R>,
 	pub refresh_task: TaskSlot<R>,
 	pub retry_task: TaskSlot<R>,
+	pub subscription_task: TaskSlot<R>,
+	pub subscriber: Option<SubscribeFn<F, R>>,
+	// validators for the current data, as returned by `Fetcher::validators`
+	pub validators: Option<Validators>,
+	pub rebase: Option<RebaseFn>,
+	// tags attached by `Fetcher::tags` for the current data
+	pub fetched_tags: Vec<Tag>,
+	history: History,
+	pub mutation: MutationState<R>,
+
+	// incremented each time a fetch is launched or the entry's data is replaced, so results can be ordered
+	version: u64,
+	// the version of the fetch or mutation which produced the entry's current data/error
+	data_version: u64,
 
 	pub(crate) strong_count: AtomicU32,
 	pub options: RwLock<StoredOptions>
@@ -82,6 +133,7 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 			key,
 
 			retry_count: AtomicU8::new(0),
+			last_retry_delay_ms: AtomicU64::new(0),
 			status: CacheEntryStatus::new(),
 			revalidate_intent: RevalidateIntent::default(),
 			data: MaybeUninit::uninit(),
@@ -90,10 +142,29 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 			base_time: Instant::now(),
 			last_draw_time_offset: AtomicU64::new(0),
 			last_request_time_offset: AtomicU64::new(u64::MAX),
+			last_fetch_time_offset: AtomicU64::new(u64::MAX),
+			data_updated_time_offset: AtomicU64::new(u64::MAX),
+			error_updated_time_offset: AtomicU64::new(u64::MAX),
+
+			fetch_count: 0,
+			last_fetch_duration: None,
+			fetch_intent: 0,
+			fetch_version: 0,
+			retry_at: None,
 
 			fetch_task: TaskSlot::new(runtime.clone()),
 			refresh_task: TaskSlot::new(runtime.clone()),
-			retry_task: TaskSlot::new(runtime),
+			retry_task: TaskSlot::new(runtime.clone()),
+			subscription_task: TaskSlot::new(runtime),
+			subscriber: None,
+			validators: None,
+			rebase: None,
+			fetched_tags: Vec::new(),
+			history: History::default(),
+			mutation: MutationState::default(),
+
+			version: 0,
+			data_version: 0,
 
 			strong_count: AtomicU32::new(0),
 			options: RwLock::new(StoredOptions::default())
@@ -148,6 +219,55 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 		&self.key
 	}
 
+	/// Returns a new version for a fetch which is about to be launched; see [`CacheEntry::is_superseded`].
+	pub fn next_version(&mut self) -> u64 {
+		self.version += 1;
+		self.version
+	}
+
+	/// Returns `true` if the entry's data or error came from a mutation or fetch newer than the fetch which was
+	/// launched with `version`, in which case that fetch's result should be discarded.
+	#[inline]
+	pub fn is_superseded(&self, version: u64) -> bool {
+		version < self.data_version
+	}
+
+	/// Returns the version of the fetch or mutation which produced the entry's current data or error.
+	#[inline]
+	pub fn data_version(&self) -> u64 {
+		self.data_version
+	}
+
+	/// Records that the entry's current data or error came from the fetch launched with `version`.
+	///
+	/// [`CacheEntry::insert`] gives the data a fresh version, superseding any fetches in progress, so the fetch task
+	/// resets it to its own version afterwards to let newer fetches still complete.
+	#[inline]
+	pub fn set_data_version(&mut self, version: u64) {
+		self.data_version = version;
+	}
+
+	/// Records that the fetch launched with `version` is in progress, due to the `RevalidateIntent` flags `intent`.
+	pub fn start_fetch(&mut self, version: u64, intent: u8) {
+		self.fetch_intent = intent;
+		self.fetch_version = version;
+	}
+
+	/// Records that the fetch launched with `version` has completed, whether or not its result was kept.
+	///
+	/// The fetch intent is left alone if a newer fetch has been launched in the meantime.
+	pub fn finish_fetch(&mut self, version: u64) {
+		if self.fetch_version == version {
+			self.fetch_intent = 0;
+		}
+	}
+
+	/// Aborts the fetch in progress, if any.
+	pub fn abort_fetch(&mut self) {
+		self.fetch_task.abort();
+		self.fetch_intent = 0;
+	}
+
 	pub fn insert<T: Send + Sync + 'static>(&mut self, data: Arc<F::Response<T>>) -> Option<CacheEntryData> {
 		self.insert_untyped(
 			data as _,
@@ -156,6 +276,21 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 		)
 	}
 
+	/// Inserts data which came from the fetcher, rebasing any local changes on top of it. Returns the data that was
+	/// actually inserted.
+	pub fn insert_fetched<T: Send + Sync + 'static>(&mut self, data: Arc<F::Response<T>>) -> Arc<F::Response<T>> {
+		let data = match &self.rebase {
+			Some(rebase) => rebase(&(Arc::clone(&data) as _))
+				.and_then(|rebased| rebased.downcast().ok())
+				.unwrap_or(data),
+			None => data
+		};
+		self.insert(Arc::clone(&data));
+		// the server's data is the new baseline; undoing past it would restore data which may be outdated
+		self.history = History::default();
+		data
+	}
+
 	pub fn insert_untyped(&mut self, data: Arc<dyn Any + Send + Sync>, #[cfg(debug_assertions)] type_name: &'static str) -> Option<CacheEntryData> {
 		self.status
 			.clear(CacheEntryStatus::LOADING | CacheEntryStatus::VALIDATING, Ordering::Relaxed); // we have mut
@@ -176,12 +311,139 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 		}
 
 		self.retry_count.store(0, Ordering::Relaxed);
-		self.last_request_time_offset
-			.store(instant_as_offset(&self.base_time, Instant::now()), Ordering::Relaxed);
+		let now = instant_as_offset(&self.base_time, Instant::now());
+		self.last_request_time_offset.store(now, Ordering::Relaxed);
+		self.data_updated_time_offset.store(now, Ordering::Relaxed);
+
+		// the new data may not correspond to the old validators; the fetch task sets new validators after inserting
+		self.validators = None;
+		self.data_version = self.next_version();
 
 		old_data
 	}
 
+	/// Records that a conditional fetch found the entry's data to be unchanged.
+	///
+	/// Like [`CacheEntry::insert`], this counts as a request and resets the entry's error & retry state, but keeps the
+	/// existing data.
+	pub fn mark_not_modified(&mut self) {
+		self.status
+			.clear(CacheEntryStatus::LOADING | CacheEntryStatus::VALIDATING, Ordering::Relaxed); // we have mut
+
+		if self.status.clear(CacheEntryStatus::HAS_ERROR, Ordering::Relaxed) {
+			unsafe { self.error.assume_init_drop() };
+		}
+
+		self.retry_count.store(0, Ordering::Relaxed);
+		// the data was confirmed to be up to date, so it counts as updated
+		let now = instant_as_offset(&self.base_time, Instant::now());
+		self.last_request_time_offset.store(now, Ordering::Relaxed);
+		self.data_updated_time_offset.store(now, Ordering::Relaxed);
+	}
+
+	/// Populates the entry with data loaded from persistent storage.
+	///
+	/// Unlike [`CacheEntry::insert`], this does not count as a request, and marks the entry as stale so it is
+	/// revalidated on first use.
+	#[cfg(feature = "persist")]
+	pub fn hydrate(&mut self, data: Arc<dyn Any + Send + Sync>, #[cfg(debug_assertions)] type_name: &'static str) {
+		let last_request_time_offset = self.last_request_time_offset.load(Ordering::Relaxed);
+		let data_updated_time_offset = self.data_updated_time_offset.load(Ordering::Relaxed);
+		self.insert_untyped(
+			data,
+			#[cfg(debug_assertions)]
+			type_name
+		);
+		self.last_request_time_offset.store(last_request_time_offset, Ordering::Relaxed);
+		self.data_updated_time_offset.store(data_updated_time_offset, Ordering::Relaxed);
+		self.revalidate_intent.add(RevalidateIntent::STALE);
+	}
+
+	/// Records `previous`, the data replaced by a mutation, in the entry's history so the mutation can be undone.
+	///
+	/// Does nothing (besides dropping any existing history) if the entry's options don't enable history.
+	pub fn record_history(&mut self, previous: Option<CacheEntryData>) {
+		let Some(depth) = self.options.read().history_depth else {
+			self.history = History::default();
+			return;
+		};
+
+		// a new mutation branches off from whatever was undone
+		self.history.redo.clear();
+		if let Some(previous) = previous {
+			self.history.undo.push_back(previous);
+			if self.history.undo.len() > usize::from(depth.get()) {
+				self.history.undo.pop_front();
+			}
+		}
+	}
+
+	#[inline]
+	pub fn can_undo(&self) -> bool {
+		!self.history.undo.is_empty()
+	}
+
+	#[inline]
+	pub fn can_redo(&self) -> bool {
+		!self.history.redo.is_empty()
+	}
+
+	/// Restores the data replaced by the most recent mutation, returning `false` if there is nothing to undo.
+	pub fn undo(&mut self) -> bool {
+		let Some(data) = self.history.undo.pop_back() else {
+			return false;
+		};
+		let current = self.insert_untyped(
+			data.value,
+			#[cfg(debug_assertions)]
+			data.type_name
+		);
+		self.history.redo.extend(current);
+		true
+	}
+
+	/// Reapplies the most recently undone mutation, returning `false` if there is nothing to redo.
+	pub fn redo(&mut self) -> bool {
+		let Some(data) = self.history.redo.pop() else {
+			return false;
+		};
+		let current = self.insert_untyped(
+			data.value,
+			#[cfg(debug_assertions)]
+			data.type_name
+		);
+		self.history.undo.extend(current);
+		true
+	}
+
+	/// Drops the entry's data and error, and marks it as stale so it is refetched on next use. Any fetch in progress
+	/// is aborted, since its result may be just as outdated.
+	///
+	/// Returns `true` if the entry had data or an error to drop.
+	pub fn invalidate(&mut self) -> bool {
+		let had_data = self.status.clear(CacheEntryStatus::HAS_DATA, Ordering::Relaxed);
+		if had_data {
+			unsafe { self.data.assume_init_drop() };
+		}
+		let had_error = self.status.clear(CacheEntryStatus::HAS_ERROR, Ordering::Relaxed);
+		if had_error {
+			unsafe { self.error.assume_init_drop() };
+		}
+
+		self.abort_fetch();
+		self.status
+			.clear(CacheEntryStatus::LOADING | CacheEntryStatus::VALIDATING, Ordering::Relaxed); // we have mut
+		// anything derived from the dropped data is outdated too, e.g. the previous data shown by `Persisted::set_key`
+		self.data_version = self.next_version();
+
+		// the data we would deduplicate against is gone
+		self.last_fetch_time_offset.store(u64::MAX, Ordering::Relaxed);
+		self.validators = None;
+		self.revalidate_intent.add(RevalidateIntent::STALE);
+
+		had_data || had_error
+	}
+
 	pub fn insert_error(&mut self, error: Arc<F::Error>) {
 		self.status
 			.clear(CacheEntryStatus::LOADING | CacheEntryStatus::VALIDATING, Ordering::Relaxed); // we have mut
@@ -191,8 +453,9 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 		}
 		self.error.write(error);
 
-		self.last_request_time_offset
-			.store(instant_as_offset(&self.base_time, Instant::now()), Ordering::Relaxed);
+		let now = instant_as_offset(&self.base_time, Instant::now());
+		self.last_request_time_offset.store(now, Ordering::Relaxed);
+		self.error_updated_time_offset.store(now, Ordering::Relaxed);
 	}
 
 	pub fn mark_used(&self) {
@@ -208,6 +471,33 @@ impl<F: Fetcher, R: Runtime> CacheEntry<F, R> {
 		}
 	}
 
+	/// Records that a fetch has successfully completed, for the purposes of deduplication.
+	pub fn mark_fetched(&self) {
+		self.last_fetch_time_offset
+			.store(instant_as_offset(&self.base_time, Instant::now()), Ordering::Release);
+	}
+
+	pub fn last_fetch_time(&self, order: Ordering) -> Option<Instant> {
+		match self.last_fetch_time_offset.load(order) {
+			u64::MAX => None,
+			offs => Some(instant_from_offset(&self.base_time, offs))
+		}
+	}
+
+	pub fn data_updated_time(&self, order: Ordering) -> Option<Instant> {
+		match self.data_updated_time_offset.load(order) {
+			u64::MAX => None,
+			offs => Some(instant_from_offset(&self.base_time, offs))
+		}
+	}
+
+	pub fn error_updated_time(&self, order: Ordering) -> Option<Instant> {
+		match self.error_updated_time_offset.load(order) {
+			u64::MAX => None,
+			offs => Some(instant_from_offset(&self.base_time, offs))
+		}
+	}
+
 	pub fn last_draw_time(&self, order: Ordering) -> Instant {
 		instant_from_offset(&self.base_time, self.last_draw_time_offset.load(order))
 	}
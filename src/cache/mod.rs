@@ -1,11 +1,18 @@
-use std::{borrow::Borrow, collections::HashMap, hash::Hash};
+use std::{
+	borrow::Borrow,
+	collections::{HashMap, HashSet},
+	hash::Hash
+};
 
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use slotmap::SlotMap;
 
 mod entry;
-pub(crate) use self::entry::{CacheEntry, CacheEntryStatus};
-use crate::{fetcher::Fetcher, runtime::Runtime};
+pub(crate) use self::entry::{CacheEntry, CacheEntryData, CacheEntryStatus, RebaseFn};
+use crate::{
+	fetcher::{Fetcher, Tag},
+	runtime::Runtime
+};
 
 slotmap::new_key_type! {
 	pub struct CacheSlot;
@@ -14,6 +21,7 @@ slotmap::new_key_type! {
 pub struct Cache<F: Fetcher, R: Runtime> {
 	runtime: R,
 	key_to_slot: RwLock<HashMap<F::Key, CacheSlot>>,
+	tag_to_slots: RwLock<HashMap<Tag, HashSet<CacheSlot>>>,
 	states: RwLock<SlotMap<CacheSlot, CacheEntry<F, R>>>
 }
 
@@ -22,6 +30,7 @@ impl<F: Fetcher, R: Runtime> Cache<F, R> {
 		Self {
 			runtime,
 			key_to_slot: RwLock::new(HashMap::new()),
+			tag_to_slots: RwLock::new(HashMap::new()),
 			states: RwLock::new(SlotMap::with_key())
 		}
 	}
@@ -55,17 +64,94 @@ impl<F: Fetcher, R: Runtime> Cache<F, R> {
 		}
 	}
 
+	pub fn get_or_create_owned(&self, key: F::Key) -> CacheSlot {
+		let key_to_slot = self.key_to_slot.upgradable_read();
+		match key_to_slot.get(&key) {
+			Some(slot) => *slot,
+			None => {
+				let mut key_to_slot = RwLockUpgradableReadGuard::upgrade(key_to_slot);
+
+				let mut results = self.states.write();
+				let slot = results.insert(CacheEntry::new(self.runtime.clone(), key.clone()));
+
+				key_to_slot.insert(key, slot);
+				slot
+			}
+		}
+	}
+
+	pub fn slots_where<P: FnMut(&F::Key) -> bool>(&self, mut predicate: P) -> Vec<CacheSlot> {
+		let key_to_slot = self.key_to_slot.read();
+		key_to_slot
+			.iter()
+			.filter_map(|(key, slot)| if predicate(key) { Some(*slot) } else { None })
+			.collect()
+	}
+
+	pub fn tag<I: IntoIterator<Item = Tag>>(&self, slot: CacheSlot, tags: I) {
+		let mut tags = tags.into_iter().peekable();
+		if tags.peek().is_none() {
+			return;
+		}
+
+		let mut tag_to_slots = self.tag_to_slots.write();
+		for tag in tags {
+			tag_to_slots.entry(tag).or_default().insert(slot);
+		}
+	}
+
+	/// Replaces the tags attached to an entry by the fetcher with `tags`. Tags which are also attached by a usage of
+	/// the entry are kept.
+	pub fn retag_fetched(&self, slot: CacheSlot, entry: &mut CacheEntry<F, R>, tags: Vec<Tag>) {
+		if entry.fetched_tags == tags {
+			return;
+		}
+
+		let mut tag_to_slots = self.tag_to_slots.write();
+		{
+			let options = entry.options.read();
+			for tag in entry.fetched_tags.iter().filter(|tag| !tags.contains(tag) && !options.has_tag(tag)) {
+				if let Some(slots) = tag_to_slots.get_mut(tag) {
+					slots.remove(&slot);
+					if slots.is_empty() {
+						tag_to_slots.remove(tag);
+					}
+				}
+			}
+		}
+		for tag in &tags {
+			tag_to_slots.entry(tag.clone()).or_default().insert(slot);
+		}
+		drop(tag_to_slots);
+
+		entry.fetched_tags = tags;
+	}
+
+	pub fn slots_tagged(&self, tags: &[&str]) -> HashSet<CacheSlot> {
+		let tag_to_slots = self.tag_to_slots.read();
+		tags.iter().filter_map(|tag| tag_to_slots.get(*tag)).flatten().copied().collect()
+	}
+
 	pub(crate) fn retain<I: FnMut(CacheSlot, &mut CacheEntry<F, R>) -> bool>(&self, mut cb: I) {
 		let mut key_to_slot = self.key_to_slot.write();
 		let mut states = self.states.write();
+		let mut removed = HashSet::new();
 		states.retain(|slot, entry| {
 			if !cb(slot, entry) {
 				key_to_slot.remove(entry.key());
+				removed.insert(slot);
 				false
 			} else {
 				true
 			}
-		})
+		});
+
+		if !removed.is_empty() {
+			self.tag_to_slots.write().retain(|_, slots| {
+				slots.retain(|slot| !removed.contains(slot));
+				!slots.is_empty()
+			});
+		}
 	}
 
 	pub fn states(&self) -> StateAccessor<'_, F, R> {
@@ -82,6 +168,11 @@ impl<F: Fetcher, R: Runtime> StateAccessor<'_, F, R> {
 		self.inner.get(slot)
 	}
 
+	#[cfg(feature = "persist")]
+	pub fn iter(&self) -> impl Iterator<Item = (CacheSlot, &CacheEntry<F, R>)> {
+		self.inner.iter()
+	}
+
 	pub fn mutate<M, T>(&mut self, slot: CacheSlot, mutator: M) -> Option<T>
 	where
 		M: FnOnce(&mut CacheEntry<F, R>) -> T
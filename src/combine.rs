@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::{error::Error, fetcher::Fetcher, result::FetchResult, runtime::Runtime};
+
+/// The combined state of multiple keys, as returned by [`SWR::get_all`][crate::SWR::get_all] or
+/// [`combine!`][crate::combine].
+///
+/// Each key is still its own entry in the cache and follows the usual lifecycle; `CombinedResult` only aggregates the
+/// results of using each key individually.
+#[derive(Clone)]
+pub struct CombinedResult<D, F: Fetcher> {
+	/// The data of every key, or `None` if any key has no data yet.
+	pub data: Option<D>,
+	/// The first error encountered by any key, in order.
+	pub error: Option<Error<F>>,
+	/// Whether or not any key is performing its initial fetch.
+	pub loading: bool,
+	/// Whether or not any key is being revalidated.
+	pub validating: bool
+}
+
+impl<D, F: Fetcher> CombinedResult<D, F> {
+	fn empty() -> Self {
+		Self {
+			data: None,
+			error: None,
+			loading: false,
+			validating: false
+		}
+	}
+
+	fn merge<T: Send + Sync + 'static, R: Runtime>(&mut self, result: &mut FetchResult<T, F, R>) {
+		self.error = self.error.take().or_else(|| result.error.take());
+		self.loading |= result.loading;
+		self.validating |= result.validating;
+	}
+}
+
+impl<T, F, R> FromIterator<FetchResult<T, F, R>> for CombinedResult<Vec<Arc<F::Response<T>>>, F>
+where
+	T: Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	fn from_iter<I: IntoIterator<Item = FetchResult<T, F, R>>>(iter: I) -> Self {
+		let mut combined = CombinedResult::empty();
+		let mut data = Some(Vec::new());
+		for mut result in iter {
+			combined.merge(&mut result);
+			match result.data.take() {
+				Some(part) => {
+					if let Some(data) = &mut data {
+						data.push(part);
+					}
+				}
+				None => data = None
+			};
+		}
+		combined.data = data;
+		combined
+	}
+}
+
+/// Combines a tuple of [`FetchResult`]s - possibly of different types - into one [`CombinedResult`].
+///
+/// This is usually used via the [`combine!`][crate::combine] macro.
+pub trait Combine<F: Fetcher> {
+	/// The type of the combined data; a tuple of each result's data.
+	type Data;
+
+	/// Combines the results.
+	fn combine(self) -> CombinedResult<Self::Data, F>;
+}
+
+macro_rules! impl_combine {
+	($($t:ident $v:ident),+) => {
+		impl<F: Fetcher, R: Runtime, $($t: Send + Sync + 'static),+> Combine<F> for ($(FetchResult<$t, F, R>,)+) {
+			type Data = ($(Arc<F::Response<$t>>,)+);
+
+			fn combine(self) -> CombinedResult<Self::Data, F> {
+				let ($(mut $v,)+) = self;
+				let mut combined = CombinedResult::empty();
+				$(combined.merge(&mut $v);)+
+				if let ($(Some($v),)+) = ($($v.data,)+) {
+					combined.data = Some(($($v,)+));
+				}
+				combined
+			}
+		}
+	};
+}
+
+impl_combine!(T1 a);
+impl_combine!(T1 a, T2 b);
+impl_combine!(T1 a, T2 b, T3 c);
+impl_combine!(T1 a, T2 b, T3 c, T4 d);
+impl_combine!(T1 a, T2 b, T3 c, T4 d, T5 e);
+impl_combine!(T1 a, T2 b, T3 c, T4 d, T5 e, T6 f);
+impl_combine!(T1 a, T2 b, T3 c, T4 d, T5 e, T6 f, T7 g);
+impl_combine!(T1 a, T2 b, T3 c, T4 d, T5 e, T6 f, T7 g, T8 h);
+
+/// Combines up to 8 [`FetchResult`]s - possibly of different types - into one [`CombinedResult`], whose data is a tuple
+/// of each result's data.
+///
+/// Works with both [`SWR::get`][crate::SWR::get] and [`Persisted::get`][crate::Persisted::get]:
+/// ```
+/// # #[derive(serde::Deserialize)]
+/// # struct User;
+/// # #[derive(serde::Deserialize)]
+/// # struct Notification;
+/// # struct App<F: swr::Fetcher> {
+/// # 	notifications: swr::Persisted<Vec<Notification>, F>
+/// # }
+/// # impl<F: swr::Fetcher<Key = String>> App<F> {
+/// # fn render(&self, swr: &swr::SWR<F>) {
+/// let dashboard = swr::combine!(swr.get::<User, _>("/me"), self.notifications.get());
+/// if let Some((user, notifications)) = dashboard.data {
+/// 	// ...
+/// }
+/// # }
+/// # }
+/// ```
+#[macro_export]
+// in edition 2021, `expr` already means `expr_2021`, which would require Rust 1.83
+#[allow(edition_2024_expr_fragment_specifier)]
+macro_rules! combine {
+	($($result:expr),+ $(,)?) => {
+		$crate::Combine::combine(($($result,)+))
+	};
+}
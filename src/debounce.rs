@@ -0,0 +1,115 @@
+use std::{borrow::Borrow, hash::Hash, sync::Arc, time::Duration};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+	SWRInner,
+	cache::CacheSlot,
+	fetcher::Fetcher,
+	options::Options,
+	result::{FetchResult, Persisted},
+	runtime::{DefaultRuntime, Runtime},
+	util::{Instant, TaskSlot, TaskStartMode}
+};
+
+/// A [persisted slot][Persisted] whose key only changes once it has settled, created with
+/// [`SWR::debounced`][crate::SWR::debounced].
+///
+/// This is useful for e.g. search-as-you-type, where fetching every intermediate query would be wasteful. A new key is
+/// only used (and thus fetched) once [`Debounced::set_key`] hasn't been called with a different key for the debounce
+/// delay. In the meantime, the last settled key's data continues to be shown; once the new key settles, its data is
+/// shown as [previous data][FetchResult::is_previous_data] until it loads.
+///
+/// Intermediate keys never get an entry in the cache, and the previously settled key is released as soon as the new
+/// key settles, allowing it to be garbage collected.
+pub struct Debounced<T: Send + Sync + 'static, F: Fetcher, R: Runtime = DefaultRuntime> {
+	persisted: Persisted<T, F, R>,
+	key: Option<F::Key>,
+	pending: Option<(F::Key, Instant)>,
+	delay: Duration,
+	// requests a redraw once the pending key settles, so it is picked up without further input
+	settle_task: TaskSlot<R>,
+	inner: Arc<SWRInner<F, R>>
+}
+
+impl<T, F, R> Debounced<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	pub(crate) fn new(inner: &Arc<SWRInner<F, R>>, delay: Duration, options: Options<F::Response<T>>) -> Self {
+		Self {
+			persisted: Persisted::new(inner, CacheSlot::default(), Some(options)),
+			key: None,
+			pending: None,
+			delay,
+			settle_task: TaskSlot::new(inner.runtime.clone()),
+			inner: Arc::clone(inner)
+		}
+	}
+
+	/// Sets the key to use once it has settled, i.e. once `set_key` hasn't been called with a different key for the
+	/// debounce delay.
+	///
+	/// Calling `set_key` with the current pending key does not restart the delay, so this can be called every frame.
+	pub fn set_key<K>(&mut self, key: &K)
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		if self.key.as_ref().is_some_and(|k| k.borrow() == key) {
+			// changed back to the settled key before the pending key could settle
+			self.pending = None;
+			self.settle_task.abort();
+			return;
+		}
+		if self.pending.as_ref().is_some_and(|(k, _)| k.borrow() == key) {
+			return;
+		}
+
+		self.pending = Some((F::Key::from(key), Instant::now()));
+
+		let inner = Arc::clone(&self.inner);
+		let delay = self.delay;
+		self.settle_task.insert(TaskStartMode::Abort, async move {
+			inner.runtime.wait(delay).await;
+			inner.hook.request_redraw();
+		});
+	}
+
+	/// Returns the settled key's entry in the cache, or an empty, non-loading result if no key has settled yet.
+	///
+	/// This should only be used during the GUI's rendering process.
+	pub fn get(&mut self) -> FetchResult<T, F, R> {
+		self.settle();
+		self.persisted.get()
+	}
+
+	fn settle(&mut self) {
+		let Some((key, _)) = self.pending.take_if(|(_, changed_at)| changed_at.elapsed() >= self.delay) else {
+			return;
+		};
+		self.persisted.set_slot(self.inner.cache.get_or_create_owned(key.clone()));
+		self.key = Some(key);
+	}
+
+	/// Returns the settled key, or `None` if no key has settled yet.
+	#[inline]
+	pub fn key(&self) -> Option<&F::Key> {
+		self.key.as_ref()
+	}
+
+	/// Returns `true` if a key has been set which has not yet settled.
+	#[inline]
+	pub fn is_pending(&self) -> bool {
+		self.pending.is_some()
+	}
+
+	/// Triggers the settled key's cache entry to revalidate.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn revalidate(&self) {
+		self.persisted.revalidate();
+	}
+}
@@ -1,7 +1,25 @@
-use std::{error::Error, fmt, future::Future, hash::Hash};
+use std::{
+	any::Any,
+	borrow::Cow,
+	error::Error,
+	fmt,
+	future::{Future, poll_fn},
+	hash::Hash,
+	ops,
+	sync::Arc,
+	task::{Poll, Waker},
+	time::Duration
+};
 
+use futures_core::Stream;
+use parking_lot::Mutex;
 use serde::de::DeserializeOwned;
 
+use crate::revalidate::RevalidateIntent;
+
+/// A tag used to group related cache entries for [invalidation][crate::SWR::invalidate_tags].
+pub type Tag = Cow<'static, str>;
+
 /// The `Fetcher` is responsible for fetching resources (likely from a remote server) when a key is not present in the
 /// cache, or needs to be revalidated.
 pub trait Fetcher: Send + Sync + 'static {
@@ -78,7 +96,7 @@ pub trait Fetcher: Send + Sync + 'static {
 	type Response<T: Send + Sync + 'static>: Send + Sync + 'static;
 
 	/// The error type returned when a fetch fails.
-	type Error: Error + Send + Sync;
+	type Error: Error + Send + Sync + 'static;
 
 	/// This fetcher's 'key' type.
 	///
@@ -168,28 +186,457 @@ pub trait Fetcher: Send + Sync + 'static {
 
 	/// Fetches the resource using the given key, deserializing the response body as type `T`.
 	fn fetch<T: DeserializeOwned + Send + Sync + 'static>(&self, key: &Self::Key) -> impl Future<Output = Result<Self::Response<T>, Self::Error>> + Send;
+
+	/// Fetches the resource using the given key, with the [reason][FetchIntent] the fetch was launched.
+	///
+	/// By default, this ignores the intent and calls [`Fetcher::fetch`].
+	#[allow(unused_variables)]
+	fn fetch_with_intent<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		key: &Self::Key,
+		intent: FetchIntent
+	) -> impl Future<Output = Result<Self::Response<T>, Self::Error>> + Send {
+		self.fetch::<T>(key)
+	}
+
+	/// Fetches the resource using the given key, with [context][FetchContext] about why & how the fetch was launched.
+	///
+	/// This is the method SWR actually calls; by default, it calls [`Fetcher::fetch_with_intent`] with the context's
+	/// [intent][FetchContext::intent]. Override it to e.g. back off based on the retry attempt, cancel work running
+	/// outside of the fetch's future when the fetch is aborted, or make a conditional request using the context's
+	/// [validators][FetchContext::validators].
+	fn fetch_with_context<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		key: &Self::Key,
+		context: &FetchContext
+	) -> impl Future<Output = Result<Fetched<Self::Response<T>>, Self::Error>> + Send {
+		async move { self.fetch_with_intent::<T>(key, context.intent()).await.map(Fetched::Data) }
+	}
+
+	/// Returns additional [tags][Tag] to attach to the key's cache entry after a successful fetch, on top of those
+	/// specified in [`Options::tags`][crate::Options::tags].
+	///
+	/// This can be used to derive tags from the response, e.g. from a `Cache-Tag` header or the IDs of the resources it
+	/// contains. By default, no tags are added.
+	#[allow(unused_variables)]
+	fn tags<T: Send + Sync + 'static>(&self, key: &Self::Key, response: &Self::Response<T>) -> Vec<Tag> {
+		Vec::new()
+	}
+
+	/// Returns the [validators][Validators] (e.g. an `ETag` header) identifying the version of the resource contained
+	/// in the response, if any.
+	///
+	/// Validators are stored alongside the key's data, and handed back to the next fetch via
+	/// [`FetchContext::validators`]. The fetcher can then make a conditional request in
+	/// [`Fetcher::fetch_with_context`], and report that the resource is unchanged by returning
+	/// [`Fetched::NotModified`]. By default, no validators are stored.
+	#[allow(unused_variables)]
+	fn validators<T: Send + Sync + 'static>(&self, key: &Self::Key, response: &Self::Response<T>) -> Option<Validators> {
+		None
+	}
+}
+
+/// The successful result of [`Fetcher::fetch_with_context`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetched<T> {
+	/// The resource was fetched.
+	Data(T),
+	/// The resource has not changed since the fetch that produced the [validators][FetchContext::validators] given to
+	/// the fetcher, e.g. because the server responded with `304 Not Modified`.
+	///
+	/// The key keeps its existing data, so anything derived from it doesn't need to be recomputed; otherwise, this
+	/// counts as a successful fetch. This must only be returned if the fetch was given validators.
+	NotModified
+}
+
+/// Opaque metadata identifying a version of a resource, used to make conditional requests.
+///
+/// Validators can be of any type, e.g. an `ETag` header or a modification date. SWR does not interpret them; it only
+/// stores them and hands them back to the [`Fetcher`]. See [`Fetcher::validators`].
+#[derive(Clone)]
+pub struct Validators(Arc<dyn Any + Send + Sync>);
+
+impl Validators {
+	/// Wraps the given validators.
+	pub fn new<V: Any + Send + Sync>(validators: V) -> Self {
+		Self(Arc::new(validators))
+	}
+
+	/// Returns a reference to the validators if they are of type `V`.
+	pub fn downcast_ref<V: Any>(&self) -> Option<&V> {
+		self.0.downcast_ref()
+	}
+}
+
+impl fmt::Debug for Validators {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Validators").finish_non_exhaustive()
+	}
+}
+
+/// A [`Fetcher`] which can also *subscribe* to keys, receiving a continuous stream of updates from a push-based source
+/// (like a WebSocket, file watcher, or IPC channel) instead of performing one-off fetches.
+///
+/// Subscriptions are used by keys retrieved with [`SWR::subscription`][crate::SWR::subscription]. The subscription is
+/// started when the key comes into use, and is torn down when the key's cache entry is garbage collected. Each item
+/// yielded by the stream replaces the key's data or error, just like the result of a fetch.
+///
+/// Subscribed keys are not automatically fetched on first use or when stale, but can still be revalidated manually,
+/// which will use [`Fetcher::fetch`].
+pub trait Subscriber: Fetcher {
+	/// Subscribes to updates for the given key, deserializing each update as type `T`.
+	///
+	/// The subscription ends when the stream returns `None`, in which case it will be restarted the next time the key
+	/// comes into use.
+	fn subscribe<T: DeserializeOwned + Send + Sync + 'static>(&self, key: &Self::Key) -> impl Stream<Item = Result<Self::Response<T>, Self::Error>> + Send;
+}
+
+/// A [`Fetcher`] which can fetch many keys in a single request, e.g. via a batch endpoint like `/users?ids=1,2,3`.
+///
+/// Batching is opt-in per response type via [`SWR::batch_type`][crate::SWR::batch_type]. Fetches of keys of that type
+/// which are launched within the same frame are then collected and dispatched together through
+/// [`BatchFetcher::fetch_many`]. Each key's result is handled as if it were fetched individually, so retries, refreshes
+/// and throttling still apply to each key separately.
+pub trait BatchFetcher: Fetcher {
+	/// Fetches all of the given keys, deserializing each response body as type `T`.
+	///
+	/// The returned `Vec` must contain one result for each key, in the same order as `keys`. Keys which are missing a
+	/// result will fall back to being fetched individually via [`Fetcher::fetch`].
+	fn fetch_many<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		keys: &[Self::Key]
+	) -> impl Future<Output = Vec<Result<Self::Response<T>, Self::Error>>> + Send;
+}
+
+/// The reason(s) a fetch was launched.
+///
+/// Multiple reasons may apply to a single fetch, e.g. if the application was focused while the key's data was stale.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FetchIntent(pub(crate) u8);
+
+impl FetchIntent {
+	/// The key was revalidated manually, e.g. via [`SWR::revalidate`][crate::SWR::revalidate].
+	pub const MANUAL: Self = Self(RevalidateIntent::MANUALLY_TRIGGERED);
+	/// The application was focused.
+	pub const FOCUS: Self = Self(RevalidateIntent::APPLICATION_FOCUSED);
+	/// A previous fetch failed and is being retried.
+	pub const RETRY: Self = Self(RevalidateIntent::RETRY_ON_ERROR);
+	/// The key was used for the first time.
+	pub const FIRST_USAGE: Self = Self(RevalidateIntent::FIRST_USAGE);
+	/// The key's [refresh interval][crate::Options::refresh_interval] elapsed.
+	pub const REFRESH_INTERVAL: Self = Self(RevalidateIntent::REFRESH_INTERVAL);
+	/// The key's data was stale, e.g. after being invalidated.
+	pub const STALE: Self = Self(RevalidateIntent::STALE);
+	/// The key was revalidated after a mutation.
+	pub const MUTATE: Self = Self(RevalidateIntent::MUTATE);
+
+	const NAMES: [(Self, &'static str); 7] = [
+		(Self::MANUAL, "MANUAL"),
+		(Self::FOCUS, "FOCUS"),
+		(Self::RETRY, "RETRY"),
+		(Self::FIRST_USAGE, "FIRST_USAGE"),
+		(Self::REFRESH_INTERVAL, "REFRESH_INTERVAL"),
+		(Self::STALE, "STALE"),
+		(Self::MUTATE, "MUTATE")
+	];
+
+	/// Returns `true` if all reasons in `other` apply to this fetch.
+	#[inline]
+	pub const fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Returns `true` if the fetch was launched automatically in the background, i.e. it wasn't triggered by manual
+	/// revalidation, a mutation, or the key coming into use.
+	#[inline]
+	pub const fn is_background(self) -> bool {
+		self.0 & (RevalidateIntent::MANUALLY_TRIGGERED | RevalidateIntent::MUTATE | RevalidateIntent::FIRST_USAGE) == 0
+	}
+}
+
+impl ops::BitOr for FetchIntent {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+impl fmt::Debug for FetchIntent {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("FetchIntent(")?;
+		let mut first = true;
+		for (flag, name) in Self::NAMES {
+			if self.contains(flag) {
+				if !first {
+					f.write_str(" | ")?;
+				}
+				f.write_str(name)?;
+				first = false;
+			}
+		}
+		f.write_str(")")
+	}
+}
+
+/// Information about a fetch, passed to [`Fetcher::fetch_with_context`] and [`Middleware::fetch`].
+#[derive(Debug, Clone, Default)]
+pub struct FetchContext {
+	pub(crate) intent: FetchIntent,
+	pub(crate) attempt: u8,
+	pub(crate) previous_data_age: Option<Duration>,
+	pub(crate) validators: Option<Validators>,
+	pub(crate) cancellation: CancellationToken
+}
+
+impl FetchContext {
+	/// Creates a new context for a fetch launched for the given reason.
+	///
+	/// This is mostly useful for calling a [`Fetcher`] outside of SWR, e.g. in tests.
+	pub fn new(intent: FetchIntent) -> Self {
+		Self { intent, ..Self::default() }
+	}
+
+	/// Returns the reason(s) this fetch was launched.
+	#[inline]
+	pub fn intent(&self) -> FetchIntent {
+		self.intent
+	}
+
+	/// Returns the number of consecutive failed fetches before this one, i.e. `0` for the first attempt and `1` for the
+	/// first retry.
+	#[inline]
+	pub fn attempt(&self) -> u8 {
+		self.attempt
+	}
+
+	/// Returns how long ago the key's current data was stored, or `None` if the key has no data.
+	#[inline]
+	pub fn previous_data_age(&self) -> Option<Duration> {
+		self.previous_data_age
+	}
+
+	/// Returns the [validators][Validators] of the key's current data, as returned by [`Fetcher::validators`], or
+	/// `None` if the key has no data.
+	#[inline]
+	pub fn validators(&self) -> Option<&Validators> {
+		self.validators.as_ref()
+	}
+
+	/// Returns a token which is cancelled when the fetch is aborted, e.g. because it was superseded or its key was
+	/// garbage collected.
+	///
+	/// Aborted fetches are dropped at their next `.await`, so the token is only needed to cancel work which happens
+	/// outside of the fetch's future, like a request running on another thread.
+	#[inline]
+	pub fn cancellation(&self) -> &CancellationToken {
+		&self.cancellation
+	}
+}
+
+/// A signal that a fetch has been aborted; see [`FetchContext::cancellation`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<Mutex<CancellationState>>);
+
+#[derive(Debug, Default)]
+struct CancellationState {
+	cancelled: bool,
+	waiters: Vec<Waker>
+}
+
+impl CancellationToken {
+	/// Returns `true` if the fetch has been aborted.
+	pub fn is_cancelled(&self) -> bool {
+		self.0.lock().cancelled
+	}
+
+	/// Waits until the fetch is aborted.
+	pub async fn cancelled(&self) {
+		poll_fn(|cx| {
+			let mut state = self.0.lock();
+			if state.cancelled {
+				return Poll::Ready(());
+			}
+			if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+				state.waiters.push(cx.waker().clone());
+			}
+			Poll::Pending
+		})
+		.await
+	}
+
+	pub(crate) fn cancel(&self) {
+		let waiters = {
+			let mut state = self.0.lock();
+			state.cancelled = true;
+			std::mem::take(&mut state.waiters)
+		};
+		for waker in waiters {
+			waker.wake();
+		}
+	}
+}
+
+/// A layer wrapping a [`Fetcher`] to observe or modify its fetches, applied with [`FetcherExt::layer`].
+///
+/// The middleware receives the inner fetcher, which it may call any number of times: zero to short-circuit the fetch,
+/// once to observe or transform its result, or more to retry it.
+///
+/// ```
+/// # use std::future::Future;
+/// # use serde::de::DeserializeOwned;
+/// use swr::{FetchContext, Fetched, Fetcher, Middleware};
+///
+/// /// Skips background refreshes while the application is in low-power mode.
+/// struct PowerSaver;
+///
+/// impl<F: Fetcher> Middleware<F> for PowerSaver
+/// where
+/// 	F::Error: From<std::io::Error>
+/// {
+/// 	async fn fetch<T: DeserializeOwned + Send + Sync + 'static>(
+/// 		&self,
+/// 		inner: &F,
+/// 		key: &F::Key,
+/// 		context: &FetchContext
+/// 	) -> Result<Fetched<F::Response<T>>, F::Error> {
+/// 		# let low_power_mode = || false;
+/// 		if context.intent().is_background() && low_power_mode() {
+/// 			return Err(std::io::Error::other("skipped in low-power mode").into());
+/// 		}
+/// 		inner.fetch_with_context::<T>(key, context).await
+/// 	}
+/// }
+/// ```
+///
+/// Middleware only applies to [`Fetcher::fetch`]; batched fetches and subscriptions are passed through to the inner
+/// fetcher as-is. A few common layers are provided in the [`layer`][crate::layer] module.
+pub trait Middleware<F: Fetcher>: Send + Sync + 'static {
+	/// Performs a fetch of `key`, usually by calling [`Fetcher::fetch_with_context`] on `inner`.
+	fn fetch<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		inner: &F,
+		key: &F::Key,
+		context: &FetchContext
+	) -> impl Future<Output = Result<Fetched<F::Response<T>>, F::Error>> + Send;
+}
+
+/// Extension methods for [`Fetcher`]s.
+pub trait FetcherExt: Fetcher + Sized {
+	/// Wraps this fetcher with the given [`Middleware`].
+	///
+	/// Layers can be stacked; the last layer applied is the outermost, i.e. it sees each fetch first.
+	fn layer<M: Middleware<Self>>(self, middleware: M) -> Layered<Self, M> {
+		Layered { inner: self, middleware }
+	}
+}
+
+impl<F: Fetcher> FetcherExt for F {}
+
+/// A [`Fetcher`] wrapped with a [`Middleware`], created by [`FetcherExt::layer`].
+#[derive(Debug, Clone)]
+pub struct Layered<F, M> {
+	inner: F,
+	middleware: M
+}
+
+impl<F, M> Layered<F, M> {
+	/// Returns the wrapped fetcher.
+	pub fn inner(&self) -> &F {
+		&self.inner
+	}
+
+	/// Returns the middleware.
+	pub fn middleware(&self) -> &M {
+		&self.middleware
+	}
+}
+
+impl<F: Fetcher, M: Middleware<F>> Fetcher for Layered<F, M> {
+	type Response<T: Send + Sync + 'static> = F::Response<T>;
+	type Error = F::Error;
+	type Key = F::Key;
+
+	fn fetch<T: DeserializeOwned + Send + Sync + 'static>(&self, key: &Self::Key) -> impl Future<Output = Result<Self::Response<T>, Self::Error>> + Send {
+		self.fetch_with_intent::<T>(key, FetchIntent::default())
+	}
+
+	async fn fetch_with_intent<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		key: &Self::Key,
+		intent: FetchIntent
+	) -> Result<Self::Response<T>, Self::Error> {
+		match self.middleware.fetch::<T>(&self.inner, key, &FetchContext::new(intent)).await? {
+			Fetched::Data(data) => Ok(data),
+			Fetched::NotModified => {
+				// the fetch wasn't given any validators, so there's no data to keep; fetch it from the inner fetcher instead
+				#[cfg(feature = "tracing")]
+				{
+					tracing::warn!(key = ?key, "middleware returned `Fetched::NotModified` for a fetch without validators");
+				}
+
+				self.inner.fetch_with_intent::<T>(key, intent).await
+			}
+		}
+	}
+
+	fn fetch_with_context<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		key: &Self::Key,
+		context: &FetchContext
+	) -> impl Future<Output = Result<Fetched<Self::Response<T>>, Self::Error>> + Send {
+		self.middleware.fetch::<T>(&self.inner, key, context)
+	}
+
+	fn tags<T: Send + Sync + 'static>(&self, key: &Self::Key, response: &Self::Response<T>) -> Vec<Tag> {
+		self.inner.tags::<T>(key, response)
+	}
+
+	fn validators<T: Send + Sync + 'static>(&self, key: &Self::Key, response: &Self::Response<T>) -> Option<Validators> {
+		self.inner.validators::<T>(key, response)
+	}
+}
+
+impl<F: Subscriber, M: Middleware<F>> Subscriber for Layered<F, M> {
+	fn subscribe<T: DeserializeOwned + Send + Sync + 'static>(&self, key: &Self::Key) -> impl Stream<Item = Result<Self::Response<T>, Self::Error>> + Send {
+		self.inner.subscribe::<T>(key)
+	}
+}
+
+impl<F: BatchFetcher, M: Middleware<F>> BatchFetcher for Layered<F, M> {
+	fn fetch_many<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		keys: &[Self::Key]
+	) -> impl Future<Output = Vec<Result<Self::Response<T>, Self::Error>>> + Send {
+		self.inner.fetch_many::<T>(keys)
+	}
 }
 
 #[cfg(test)]
+#[allow(dead_code)]
 pub(crate) mod mock {
 	use std::{
 		fmt,
 		marker::PhantomData,
+		pin::Pin,
 		sync::{
 			Arc,
 			atomic::{AtomicUsize, Ordering}
 		},
+		task::{Context, Poll},
 		time::Duration
 	};
 
-	use tokio::time::sleep;
+	use parking_lot::Mutex;
+	use tokio::{sync::mpsc, time::sleep};
 
-	#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+	#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 	pub enum Key {
 		Basic,
 		Delayed(Duration),
 		AlwaysError,
-		ErrorNTimes(usize)
+		ErrorNTimes(usize),
+		Page(usize)
 	}
 
 	impl From<&Key> for Key {
@@ -209,10 +656,20 @@ pub(crate) mod mock {
 
 	impl std::error::Error for Error {}
 
+	impl From<crate::layer::TimedOut> for Error {
+		fn from(_: crate::layer::TimedOut) -> Self {
+			Error
+		}
+	}
+
 	#[derive(Default)]
 	struct FetcherInner {
 		fetch_count: AtomicUsize,
-		error_count: AtomicUsize
+		batch_count: AtomicUsize,
+		error_count: AtomicUsize,
+		subscribers: Mutex<Vec<mpsc::UnboundedSender<usize>>>,
+		// tags attached to `Key::Basic`
+		tags: Mutex<Vec<super::Tag>>
 	}
 
 	pub struct Fetcher<E = Error>(Arc<FetcherInner>, PhantomData<E>);
@@ -240,6 +697,22 @@ pub(crate) mod mock {
 			self.0.fetch_count.load(Ordering::Acquire)
 		}
 
+		pub fn batch_count(&self) -> usize {
+			self.0.batch_count.load(Ordering::Acquire)
+		}
+
+		/// Sets the tags attached to `Key::Basic` by subsequent fetches.
+		pub fn set_tags(&self, tags: Vec<super::Tag>) {
+			*self.0.tags.lock() = tags;
+		}
+
+		/// Sends `value` to all active subscriptions, returning the number of subscriptions which received it.
+		pub fn push(&self, value: usize) -> usize {
+			let mut subscribers = self.0.subscribers.lock();
+			subscribers.retain(|tx| tx.send(value).is_ok());
+			subscribers.len()
+		}
+
 		pub fn reset(&self) {
 			self.0.fetch_count.store(0, Ordering::Release);
 			self.0.error_count.store(0, Ordering::Release);
@@ -269,7 +742,53 @@ pub(crate) mod mock {
 						Err(E::default())
 					}
 				}
+				Key::Page(n) => serde_json::from_value(serde_json::json!(n)).map_err(|_| E::default())
 			}
 		}
+
+		fn tags<T: Send + Sync + 'static>(&self, key: &Self::Key, _response: &Self::Response<T>) -> Vec<super::Tag> {
+			match key {
+				Key::Basic => self.0.tags.lock().clone(),
+				Key::Page(n) => vec![format!("page:{n}").into()],
+				_ => Vec::new()
+			}
+		}
+	}
+
+	impl<E: std::error::Error + Default + Sync + Send + 'static> super::BatchFetcher for Fetcher<E> {
+		async fn fetch_many<T: serde::de::DeserializeOwned + Send + Sync + 'static>(&self, keys: &[Self::Key]) -> Vec<Result<Self::Response<T>, Self::Error>> {
+			use super::Fetcher as _;
+
+			self.0.batch_count.fetch_add(1, Ordering::AcqRel);
+
+			let mut results = Vec::with_capacity(keys.len());
+			for key in keys {
+				results.push(self.fetch::<T>(key).await);
+			}
+			results
+		}
+	}
+
+	pub struct Subscription<T, E>(mpsc::UnboundedReceiver<usize>, PhantomData<fn() -> (T, E)>);
+
+	impl<T: serde::de::DeserializeOwned, E: Default> futures_core::Stream for Subscription<T, E> {
+		type Item = Result<T, E>;
+
+		fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+			self.0
+				.poll_recv(cx)
+				.map(|value| value.map(|value| serde_json::from_value(serde_json::json!(value)).map_err(|_| E::default())))
+		}
+	}
+
+	impl<E: std::error::Error + Default + Sync + Send + 'static> super::Subscriber for Fetcher<E> {
+		fn subscribe<T: serde::de::DeserializeOwned + Send + Sync + 'static>(
+			&self,
+			_key: &Self::Key
+		) -> impl futures_core::Stream<Item = Result<Self::Response<T>, Self::Error>> + Send {
+			let (tx, rx) = mpsc::unbounded_channel();
+			self.0.subscribers.lock().push(tx);
+			Subscription(rx, PhantomData)
+		}
 	}
 }
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+	SWRInner,
+	error::Error,
+	fetcher::Fetcher,
+	options::Options,
+	result::Persisted,
+	runtime::{DefaultRuntime, Runtime}
+};
+
+type KeyGetter<T, F> = Box<dyn Fn(usize, Option<&<F as Fetcher>::Response<T>>) -> Option<<F as Fetcher>::Key> + Send + Sync>;
+
+/// A paginated query, created with [`SWR::infinite`][crate::SWR::infinite].
+///
+/// `Infinite` holds a [persisted slot][Persisted] for each loaded page; like `Persisted`, it is meant to be stored
+/// across renders.
+///
+/// # Revalidation
+/// Each page is its own entry in the cache and follows the usual lifecycle, with two exceptions:
+/// - Only the first page is revalidated when the application is focused.
+/// - [`Infinite::revalidate`] revalidates *all* loaded pages.
+pub struct Infinite<T: Send + Sync + 'static, F: Fetcher, R: Runtime = DefaultRuntime> {
+	get_key: KeyGetter<T, F>,
+	pages: Vec<(F::Key, Persisted<T, F, R>)>,
+	size: usize,
+	options: Options<F::Response<T>>,
+	inner: Arc<SWRInner<F, R>>
+}
+
+impl<T, F, R> Infinite<T, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	pub(crate) fn new<G>(inner: &Arc<SWRInner<F, R>>, options: Options<F::Response<T>>, get_key: G) -> Self
+	where
+		G: Fn(usize, Option<&F::Response<T>>) -> Option<F::Key> + Send + Sync + 'static
+	{
+		Self {
+			get_key: Box::new(get_key),
+			pages: Vec::new(),
+			size: 1,
+			options,
+			inner: Arc::clone(inner)
+		}
+	}
+
+	/// Returns all pages loaded so far.
+	///
+	/// Pages are requested in order; the key for page `n` is only generated once page `n - 1` has data.
+	///
+	/// This should only be used during the GUI's rendering process.
+	pub fn get(&mut self) -> InfiniteResult<T, F> {
+		let mut result = InfiniteResult {
+			pages: Vec::with_capacity(self.size),
+			error: None,
+			loading: false,
+			validating: false,
+			reached_end: false
+		};
+
+		let mut previous: Option<Arc<F::Response<T>>> = None;
+		for index in 0..self.size {
+			if index > 0 && previous.is_none() {
+				// Can't generate the next key until the previous page has loaded.
+				break;
+			}
+
+			let Some(key) = (self.get_key)(index, previous.as_deref()) else {
+				result.reached_end = true;
+				self.pages.truncate(index);
+				break;
+			};
+
+			if self.pages.get(index).is_none_or(|(k, _)| *k != key) {
+				self.pages.truncate(index);
+
+				let slot = self.inner.cache.get_or_create_owned(key.clone());
+				let page = Persisted::new(&self.inner, slot, None);
+				page.register(&self.options);
+				self.pages.push((key, page));
+			}
+
+			let page = self.pages[index].1.get_inner(self.inner.cache.states(), true, index == 0);
+			let data = match page.data {
+				Some(data) => Some(data),
+				None if index == 0 => self.options.fallback.clone(),
+				None => None
+			};
+
+			result.error = result.error.or(page.error);
+			result.loading |= page.loading;
+			result.validating |= page.validating;
+			if let Some(data) = &data {
+				result.pages.push(Arc::clone(data));
+			}
+			previous = data;
+		}
+
+		result
+	}
+
+	/// Returns the number of pages that will be requested.
+	#[inline]
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Sets the number of pages to request.
+	///
+	/// Pages beyond the new size are released, allowing them to be garbage collected.
+	pub fn set_size(&mut self, size: usize) {
+		self.size = size;
+		self.pages.truncate(size);
+		self.inner.hook.request_redraw();
+	}
+
+	/// Requests one more page.
+	#[inline]
+	pub fn load_more(&mut self) {
+		self.set_size(self.size + 1);
+	}
+
+	/// Triggers every loaded page to revalidate.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn revalidate(&self) {
+		for (_, page) in &self.pages {
+			page.revalidate();
+		}
+	}
+}
+
+/// The state of a paginated query, as returned by [`Infinite::get`].
+#[derive(Clone)]
+pub struct InfiniteResult<T: Send + Sync + 'static, F: Fetcher> {
+	/// The data of each page loaded so far, in order.
+	pub pages: Vec<Arc<F::Response<T>>>,
+	/// The first error encountered when fetching pages, if any.
+	pub error: Option<Error<F>>,
+	/// Whether or not any requested page is performing its initial fetch.
+	pub loading: bool,
+	/// Whether or not any loaded page is being revalidated.
+	pub validating: bool,
+	/// Whether or not the key generator signaled that there are no more pages.
+	pub reached_end: bool
+}
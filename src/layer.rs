@@ -0,0 +1,230 @@
+//! Built-in [`Middleware`] for use with [`FetcherExt::layer`][crate::FetcherExt::layer].
+//!
+//! SWR provides the following layers:
+//! - **[`Timeout`]** - fails fetches which take too long
+//! - **[`ConcurrencyLimit`]** - limits the number of fetches in flight at once
+//! - **[`Trace`]** - logs each fetch via [`tracing`] (available with the `tracing` Cargo feature)
+//! - *write your own by implementing [`Middleware`]!*
+//!
+//! Layers don't box the futures they wrap, so stacking them is cheap:
+//! ```
+//! # use std::time::Duration;
+//! use swr::{
+//! 	FetcherExt,
+//! 	layer::{ConcurrencyLimit, Timeout}
+//! };
+//! # mod my_app {
+//! # 	use serde::de::DeserializeOwned;
+//! # 	#[derive(Debug)]
+//! # 	pub struct Error;
+//! # 	impl std::fmt::Display for Error {
+//! # 		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("error") }
+//! # 	}
+//! # 	impl std::error::Error for Error {}
+//! # 	impl From<swr::layer::TimedOut> for Error {
+//! # 		fn from(_: swr::layer::TimedOut) -> Self { Error }
+//! # 	}
+//! # 	pub struct Fetcher;
+//! # 	impl swr::Fetcher for Fetcher {
+//! # 		type Response<T: Send + Sync + 'static> = T;
+//! # 		type Error = Error;
+//! # 		type Key = String;
+//! # 		async fn fetch<T: DeserializeOwned + Send + Sync + 'static>(&self, _: &String) -> Result<T, Error> { Err(Error) }
+//! # 	}
+//! # }
+//!
+//! // `my_app::Error` implements `From<swr::layer::TimedOut>`
+//! let fetcher = my_app::Fetcher
+//! 	.layer(Timeout::new(Duration::from_secs(10), swr::runtime::Tokio))
+//! 	.layer(ConcurrencyLimit::new(4));
+//! ```
+
+use std::{
+	error::Error,
+	fmt,
+	future::{Future, poll_fn},
+	pin::pin,
+	sync::Arc,
+	task::{Poll, Waker},
+	time::Duration
+};
+
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "tracing")]
+use crate::util::Instant;
+use crate::{
+	fetcher::{FetchContext, Fetched, Fetcher, Middleware},
+	runtime::Runtime
+};
+
+/// The error produced by the [`Timeout`] layer when a fetch takes too long.
+///
+/// To use `Timeout`, the fetcher's [`Error`][Fetcher::Error] type must implement `From<TimedOut>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("fetch timed out")
+	}
+}
+
+impl Error for TimedOut {}
+
+/// Fails fetches which take longer than a set duration with [`TimedOut`].
+///
+/// A timed out fetch is treated like any other error, so it will be [retried][crate::Options::error_retry_interval].
+#[derive(Debug, Clone)]
+pub struct Timeout<R: Runtime> {
+	duration: Duration,
+	runtime: R
+}
+
+impl<R: Runtime> Timeout<R> {
+	/// Creates a new timeout layer, using `runtime` to keep time.
+	pub fn new(duration: Duration, runtime: R) -> Self {
+		Self { duration, runtime }
+	}
+}
+
+impl<F, R> Middleware<F> for Timeout<R>
+where
+	F: Fetcher,
+	F::Error: From<TimedOut>,
+	R: Runtime
+{
+	async fn fetch<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		inner: &F,
+		key: &F::Key,
+		context: &FetchContext
+	) -> Result<Fetched<F::Response<T>>, F::Error> {
+		let mut fetch = pin!(inner.fetch_with_context::<T>(key, context));
+		let mut timeout = pin!(self.runtime.wait(self.duration));
+		poll_fn(|cx| {
+			if let Poll::Ready(res) = fetch.as_mut().poll(cx) {
+				return Poll::Ready(res);
+			}
+			timeout.as_mut().poll(cx).map(|_| Err(TimedOut.into()))
+		})
+		.await
+	}
+}
+
+/// Limits the number of fetches in flight at once. Further fetches wait for a running fetch to complete.
+///
+/// Clones of a `ConcurrencyLimit` share the same limit, so one limit can be applied to multiple fetchers.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+	max: usize,
+	state: Arc<Mutex<LimitState>>
+}
+
+#[derive(Debug, Default)]
+struct LimitState {
+	active: usize,
+	waiters: Vec<Waker>
+}
+
+impl ConcurrencyLimit {
+	/// Creates a new concurrency limit allowing up to `max` fetches at once.
+	///
+	/// # Panics
+	/// Panics if `max` is `0`.
+	pub fn new(max: usize) -> Self {
+		assert!(max > 0, "concurrency limit must be at least 1");
+		Self { max, state: Arc::default() }
+	}
+
+	/// Returns the number of fetches currently in flight.
+	pub fn active(&self) -> usize {
+		self.state.lock().active
+	}
+
+	async fn acquire(&self) -> Permit<'_> {
+		poll_fn(|cx| {
+			let mut state = self.state.lock();
+			if state.active < self.max {
+				state.active += 1;
+				return Poll::Ready(Permit(&self.state));
+			}
+			if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+				state.waiters.push(cx.waker().clone());
+			}
+			Poll::Pending
+		})
+		.await
+	}
+}
+
+/// Releases its slot in a [`ConcurrencyLimit`] when dropped, even if the fetch is aborted.
+struct Permit<'l>(&'l Mutex<LimitState>);
+
+impl Drop for Permit<'_> {
+	fn drop(&mut self) {
+		let waiters = {
+			let mut state = self.0.lock();
+			state.active -= 1;
+			// Wake every waiter rather than just one, since a woken waiter may have been aborted in the meantime.
+			std::mem::take(&mut state.waiters)
+		};
+		for waker in waiters {
+			waker.wake();
+		}
+	}
+}
+
+impl<F: Fetcher> Middleware<F> for ConcurrencyLimit {
+	async fn fetch<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		inner: &F,
+		key: &F::Key,
+		context: &FetchContext
+	) -> Result<Fetched<F::Response<T>>, F::Error> {
+		let permit = self.acquire().await;
+		let res = inner.fetch_with_context::<T>(key, context).await;
+		drop(permit);
+		res
+	}
+}
+
+/// Logs each fetch's key, intent, attempt, duration and result via [`tracing`].
+///
+/// SWR already logs fetches with the `tracing` feature enabled; this layer is useful for logging at a different point
+/// in a middleware stack, e.g. to measure the time spent waiting on a [`ConcurrencyLimit`].
+#[derive(Debug, Clone, Default)]
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub struct Trace {
+	name: &'static str
+}
+
+#[cfg(feature = "tracing")]
+impl Trace {
+	/// Creates a new tracing layer. `name` is included in each log message to tell multiple layers apart.
+	pub fn new(name: &'static str) -> Self {
+		Self { name }
+	}
+}
+
+#[cfg(feature = "tracing")]
+impl<F: Fetcher> Middleware<F> for Trace {
+	async fn fetch<T: DeserializeOwned + Send + Sync + 'static>(
+		&self,
+		inner: &F,
+		key: &F::Key,
+		context: &FetchContext
+	) -> Result<Fetched<F::Response<T>>, F::Error> {
+		tracing::debug!(layer = self.name, key = ?key, intent = ?context.intent(), attempt = context.attempt(), "fetch started");
+		let before = Instant::now();
+		let res = inner.fetch_with_context::<T>(key, context).await;
+		match &res {
+			Ok(Fetched::Data(_)) => tracing::info!(layer = self.name, key = ?key, "OK {}ms", before.elapsed().as_millis()),
+			Ok(Fetched::NotModified) => tracing::info!(layer = self.name, key = ?key, "NOT MODIFIED {}ms", before.elapsed().as_millis()),
+			Err(e) => tracing::info!(layer = self.name, key = ?key, "ERR {}ms: {e}", before.elapsed().as_millis())
+		}
+		res
+	}
+}
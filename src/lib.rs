@@ -16,6 +16,12 @@
 //! Along with manually triggering revalidations, keys can also be **mutated** to either immediately override the data
 //! stored in the cache, or modify it based on the result of an async task.
 //!
+//! Data which comes from a push-based source rather than one-off requests can instead be retrieved with
+//! [`SWR::subscription`] if the `Fetcher` also implements [`Subscriber`].
+//!
+//! Cross-cutting behavior like timeouts, logging, or authentication can be added to a `Fetcher` by wrapping it with
+//! [`Middleware`] via [`FetcherExt::layer`]; see the [`layer`] module for built-in layers.
+//!
 //! # Hooks
 //! SWR provides [`Hook`] implementations for the following GUI libraries:
 //! - **[`egui`]** - [`hook::Egui`] (available with the `egui` Cargo feature)
@@ -39,6 +45,9 @@
 //!
 //! # Other Cargo features
 //! - **`tracing`**: Enables logging when fetches occur/cache entries are garbage collected, via [`tracing`].
+//! - **`persist`**: Enables saving the cache to a [`Storage`][persist::Storage] backend and restoring it on startup via
+//!   [`SWR::persist`] & [`SWR::hydrate`].
+//! - **`fs`**: Enables [`persist::FileStorage`], which persists the cache to a file. Implies `persist`.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(rust_2024_compatibility)]
@@ -46,46 +55,71 @@
 #![warn(missing_docs)]
 
 use std::{
+	any::TypeId,
 	borrow::Borrow,
+	collections::HashMap,
 	future::Future,
 	hash::Hash,
-	sync::{Arc, atomic::Ordering}
+	sync::{Arc, atomic::Ordering},
+	time::Duration
 };
 
+use parking_lot::RwLock;
 use serde::de::DeserializeOwned;
 
+pub(crate) mod batch;
 pub(crate) mod cache;
+pub(crate) mod combine;
+pub(crate) mod debounce;
 pub(crate) mod error;
 pub(crate) mod fetcher;
 pub mod hook;
+pub(crate) mod infinite;
+pub mod layer;
+pub(crate) mod mutation;
 pub(crate) mod options;
+#[cfg(feature = "persist")]
+#[cfg_attr(docsrs, doc(cfg(feature = "persist")))]
+pub mod persist;
+pub(crate) mod queue;
 pub(crate) mod result;
 pub(crate) mod revalidate;
 pub mod runtime;
+pub(crate) mod transaction;
 pub(crate) mod util;
 
 #[cfg(test)]
 mod tests;
 
 use self::{
-	cache::{Cache, CacheEntryStatus, CacheSlot},
-	revalidate::RevalidateIntent,
+	batch::{AnyBatch, Batch},
+	cache::{Cache, CacheEntryData, CacheEntryStatus, CacheSlot},
+	revalidate::{RevalidateIntent, launch_subscription},
 	runtime::{DefaultRuntime, RuntimeDefault}
 };
 pub use self::{
+	combine::{Combine, CombinedResult},
+	debounce::Debounced,
 	error::{Error, MismatchedTypeError},
-	fetcher::Fetcher,
+	fetcher::{BatchFetcher, CancellationToken, FetchContext, FetchIntent, Fetched, Fetcher, FetcherExt, Layered, Middleware, Subscriber, Tag, Validators},
 	hook::Hook,
-	options::{MutateOptions, Options},
-	result::{FetchResult as Result, Persisted},
-	runtime::Runtime
+	infinite::{Infinite, InfiniteResult},
+	mutation::{Mutation, TriggerPolicy},
+	options::{Callbacks, CustomRetryPolicy, MutateOptions, Options, RetryPolicy},
+	queue::{Command, MutationQueue},
+	result::{FetchResult as Result, FetchStatus, Persisted, Selector, Settled},
+	runtime::Runtime,
+	transaction::Transaction
 };
 
 pub(crate) struct SWRInner<F: Fetcher, R: Runtime> {
 	fetcher: F,
 	runtime: R,
 	hook: Box<dyn Hook>,
-	cache: Cache<F, R>
+	cache: Cache<F, R>,
+	batches: RwLock<HashMap<TypeId, Arc<dyn AnyBatch>>>,
+	#[cfg(feature = "persist")]
+	codecs: RwLock<HashMap<TypeId, persist::Codec>>
 }
 
 impl<F: Fetcher, R: Runtime> SWRInner<F, R> {
@@ -94,10 +128,26 @@ impl<F: Fetcher, R: Runtime> SWRInner<F, R> {
 			fetcher,
 			runtime: runtime.clone(),
 			hook: Box::new(hook) as Box<dyn Hook>,
-			cache: Cache::new(runtime)
+			cache: Cache::new(runtime),
+			batches: RwLock::default(),
+			#[cfg(feature = "persist")]
+			codecs: RwLock::default()
 		}
 	}
 
+	pub(crate) fn batch<T>(&self) -> Option<Arc<Batch<T, F, R>>>
+	where
+		T: serde::de::DeserializeOwned + Send + Sync + 'static
+	{
+		let batches = self.batches.read();
+		if batches.is_empty() {
+			return None;
+		}
+		let batch = Arc::clone(batches.get(&TypeId::of::<T>())?);
+		drop(batches);
+		batch.into_any().downcast().ok()
+	}
+
 	pub(crate) fn revalidate(&self, slot: CacheSlot) {
 		let states = self.cache.states();
 		let Some(state) = states.get(slot) else {
@@ -113,7 +163,8 @@ impl<F: Fetcher, R: Runtime> SWRInner<F, R> {
 	{
 		let mut states = self.cache.states();
 		states.mutate(slot, |state| {
-			state.insert(data);
+			let previous_data = state.insert(data);
+			state.record_history(previous_data);
 			self.hook.request_redraw();
 		});
 	}
@@ -132,52 +183,225 @@ impl<F: Fetcher, R: Runtime> SWRInner<F, R> {
 		Fut: Future<Output = std::result::Result<U, E>> + Send
 	{
 		let inner = Arc::clone(self);
-		self.runtime.spawn(async move {
-			let previous_data = {
-				let mut states = inner.cache.states();
-				states
-					.mutate(slot, |state| {
-						if let Some(optimistic_data) = options.optimistic_data {
-							let old_data = state.insert(optimistic_data);
-							inner.hook.request_redraw();
-							old_data
-						} else {
-							state.data_untyped().cloned()
-						}
-					})
-					.flatten()
-			};
+		self.runtime.spawn(async move { inner.run_mutation(slot, options, None, mutator).await })
+	}
 
-			let mut res = mutator(previous_data.as_ref().and_then(|c| c.value.downcast_ref()), &inner.fetcher).await;
+	pub(crate) async fn run_mutation<T, U, M, E, Fut>(
+		self: Arc<Self>,
+		slot: CacheSlot,
+		options: MutateOptions<F::Response<T>, U>,
+		// for `TriggerPolicy::Latest`; the result is discarded if another trigger of the key's `Mutation` came after
+		latest_generation: Option<u64>,
+		mutator: M
+	) -> std::result::Result<U, E>
+	where
+		T: Send + Sync + 'static,
+		U: Send,
+		M: FnOnce(Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		let optimistic = options.optimistic_data.is_some();
+		let previous_data = {
+			let mut states = self.cache.states();
+			states
+				.mutate(slot, |state| {
+					let previous_data = if let Some(optimistic_data) = options.optimistic_data {
+						let old_data = state.insert(optimistic_data);
+						self.hook.request_redraw();
+						old_data
+					} else {
+						state.data_untyped().cloned()
+					};
+					if latest_generation.is_none() {
+						return previous_data;
+					}
+					// the data may be the optimistic data of an earlier trigger, which is discarded once this one starts
+					state.mutation.latest_base.get_or_insert(previous_data).clone()
+				})
+				.flatten()
+		};
+
+		// the entry stores the response itself, so it must be downcast as an `Arc` to be passed on as one
+		let previous_typed = previous_data
+			.as_ref()
+			.and_then(|c| Arc::clone(&c.value).downcast::<F::Response<T>>().ok());
+		let mut res = mutator(previous_typed.as_ref(), &self.fetcher).await;
+
+		{
+			let mut states = self.cache.states();
+			states.mutate(slot, |state| {
+				if let Some(generation) = latest_generation {
+					if generation != state.mutation.generation {
+						return;
+					}
+					state.mutation.latest_base = None;
+				}
+
+				// If we're currently in the middle of a fetch, cancel it since it's probably outdated.
+				state.abort_fetch();
+
+				if let Ok(data) = &mut res {
+					let old_data = state.insert((options.populator)(data, previous_typed.as_ref()));
+					if options.record_history {
+						// optimistic data is only an intermediate state; undoing returns to the data from before the mutation
+						state.record_history(if optimistic { previous_data.clone() } else { old_data });
+					}
+					if options.revalidate {
+						state.revalidate_intent().add(RevalidateIntent::MUTATE);
+					}
+				} else if options.rollback_on_error {
+					if let Some(previous_data) = previous_data {
+						state.insert_untyped(
+							previous_data.value,
+							#[cfg(debug_assertions)]
+							previous_data.type_name
+						);
+					}
+				}
+
+				self.hook.request_redraw();
+			});
+		}
+
+		res
+	}
+
+	pub(crate) fn transaction<U, M, E, Fut>(
+		self: &Arc<Self>,
+		staged: Vec<(CacheSlot, CacheEntryData)>,
+		revalidate: bool,
+		mutator: M
+	) -> R::Task<std::result::Result<U, E>>
+	where
+		U: Send,
+		M: FnOnce(&F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		// the data each touched entry had before the transaction, and the version of its optimistic data; a key staged
+		// twice is only snapshotted once
+		let mut snapshots: Vec<(CacheSlot, Option<CacheEntryData>, u64)> = Vec::with_capacity(staged.len());
+		{
+			let mut states = self.cache.states();
+			for (slot, data) in staged {
+				let Some((previous_data, version)) = states.mutate(slot, |state| {
+					let previous_data = state.insert_untyped(
+						data.value,
+						#[cfg(debug_assertions)]
+						data.type_name
+					);
+					(previous_data, state.data_version())
+				}) else {
+					continue;
+				};
+				match snapshots.iter_mut().find(|(s, ..)| *s == slot) {
+					Some((_, _, staged_version)) => *staged_version = version,
+					None => snapshots.push((slot, previous_data, version))
+				}
+			}
+		}
+		self.hook.request_redraw();
+
+		let inner = Arc::clone(self);
+		self.runtime.spawn(async move {
+			let res = mutator(&inner.fetcher).await;
 
 			{
 				let mut states = inner.cache.states();
-				states.mutate(slot, |state| {
-					// If we're currently in the middle of a fetch, cancel it since it's probably outdated.
-					state.fetch_task.abort();
-
-					if let Ok(data) = &mut res {
-						state.insert((options.populator)(data, previous_data.as_ref().and_then(|c| c.value.downcast_ref())));
-						if options.revalidate {
-							state.revalidate_intent().add(RevalidateIntent::MUTATE);
+				for (slot, previous_data, version) in snapshots {
+					states.mutate(slot, |state| {
+						if res.is_err() && state.data_version() != version {
+							// a fetch or mutation replaced the optimistic data in the meantime; keep its newer data
+							return;
 						}
-					} else if options.rollback_on_error {
-						if let Some(previous_data) = previous_data {
+
+						// If we're currently in the middle of a fetch, cancel it since it's probably outdated.
+						state.abort_fetch();
+
+						if res.is_ok() {
+							state.record_history(previous_data);
+							if revalidate {
+								state.revalidate_intent().add(RevalidateIntent::MUTATE);
+							}
+						} else if let Some(previous_data) = previous_data {
 							state.insert_untyped(
 								previous_data.value,
 								#[cfg(debug_assertions)]
 								previous_data.type_name
 							);
+						} else {
+							// the entry had no data before the transaction
+							state.invalidate();
 						}
-					}
-
-					inner.hook.request_redraw();
-				});
+					});
+				}
 			}
+			inner.hook.request_redraw();
 
 			res
 		})
 	}
+
+	/// Undoes (or redoes) the most recent mutation of the slot, returning the version of the restored data, or `None`
+	/// if there was nothing to undo.
+	///
+	/// If `expected_version` is given, the slot is only stepped if its data is still of that version.
+	pub(crate) fn step_history(&self, slot: CacheSlot, redo: bool, expected_version: Option<u64>) -> Option<u64> {
+		let mut states = self.cache.states();
+		let version = states
+			.mutate(slot, |state| {
+				if expected_version.is_some_and(|version| version != state.data_version()) {
+					return None;
+				}
+				let stepped = if redo { state.redo() } else { state.undo() };
+				stepped.then(|| state.data_version())
+			})
+			.flatten();
+		drop(states);
+
+		if version.is_some() {
+			self.hook.request_redraw();
+		}
+		version
+	}
+
+	pub(crate) fn step_history_with<T, U, M, E, Fut>(
+		self: &Arc<Self>,
+		slot: CacheSlot,
+		redo: bool,
+		options: MutateOptions<F::Response<T>, U>,
+		mutator: M
+	) -> Option<R::Task<std::result::Result<U, E>>>
+	where
+		T: Send + Sync + 'static,
+		U: Send,
+		M: FnOnce(Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		let version = self.step_history(slot, redo, None)?;
+
+		// the restored data is already in place, so there's nothing to display optimistically; rolling back means
+		// stepping back through the history instead of reinserting the restored data
+		let rollback_on_error = options.rollback_on_error;
+		let options = MutateOptions {
+			optimistic_data: None,
+			rollback_on_error: false,
+			record_history: false,
+			..options
+		};
+
+		let inner = Arc::clone(self);
+		Some(self.runtime.spawn(async move {
+			let res = Arc::clone(&inner).run_mutation(slot, options, None, mutator).await;
+			// don't roll back over anything that replaced the restored data in the meantime
+			if res.is_err() && rollback_on_error {
+				inner.step_history(slot, !redo, Some(version));
+			}
+			res
+		}))
+	}
 }
 
 /// An SWR cache.
@@ -217,7 +441,13 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 			let weak_inner = Arc::downgrade(&inner);
 			inner.hook.register_end_frame_cb(Box::new(move || {
 				if let Some(inner) = weak_inner.upgrade() {
+					for batch in inner.batches.read().values() {
+						batch.flush();
+					}
+
 					inner.cache.retain(|_, state| {
+						state.options.write().end_frame();
+
 						let status = state.status();
 						let used = status.clear(CacheEntryStatus::USED_THIS_PASS, Ordering::AcqRel);
 						if !used {
@@ -233,9 +463,10 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 										tracing::info!(key = ?state.key(), "clearing entry because it exceeded GC timeout");
 									}
 
-									state.fetch_task.abort();
+									state.abort_fetch();
 									state.refresh_task.abort();
 									state.retry_task.abort();
+									state.subscription_task.abort();
 
 									return false;
 								}
@@ -252,6 +483,22 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		Self { inner }
 	}
 
+	/// Opts keys with response type `T` in to [batched fetching][BatchFetcher].
+	///
+	/// Fetches of these keys launched during a frame are collected and dispatched together via
+	/// [`BatchFetcher::fetch_many`] at the end of the frame. Fetches launched outside of rendering (i.e. by interval
+	/// refreshes or error retries) are dispatched after `window` at most.
+	pub fn batch_type<T>(&self, window: Duration)
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		F: BatchFetcher
+	{
+		self.inner
+			.batches
+			.write()
+			.insert(TypeId::of::<T>(), Arc::new(Batch::<T, F, R>::new(window, self.inner.runtime.clone())));
+	}
+
 	/// Returns a [persisted cache slot][Persisted] for the given key.
 	///
 	/// Persisted slots are meant to be stored across renders; they are thus more performant than the more
@@ -285,6 +532,36 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		Persisted::<T, F, R>::new(&self.inner, self.inner.cache.get_or_create(key), None).get()
 	}
 
+	/// Returns the combined entries in the cache of every key in `keys`, using the default [options][Options].
+	///
+	/// Each key is used as if by [`SWR::get`]. The result's [data][CombinedResult::data] is only present once every key
+	/// has data. For keys of different types, see [`combine!`].
+	///
+	/// This should only be used during the GUI's rendering process.
+	pub fn get_all<'k, T, K, I>(&self, keys: I) -> CombinedResult<Vec<Arc<F::Response<T>>>, F>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized + 'k,
+		F::Key: Borrow<K> + for<'a> From<&'a K>,
+		I: IntoIterator<Item = &'k K>
+	{
+		keys.into_iter().map(|key| self.get::<T, K>(key)).collect()
+	}
+
+	/// Returns the combined entries in the cache of every key in `keys`.
+	///
+	/// See [`SWR::get_all`] for more information.
+	pub fn get_all_with<'k, T, K, I>(&self, keys: I, options: Options<F::Response<T>>) -> CombinedResult<Vec<Arc<F::Response<T>>>, F>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized + 'k,
+		F::Key: Borrow<K> + for<'a> From<&'a K>,
+		I: IntoIterator<Item = &'k K>,
+		Options<F::Response<T>>: Clone
+	{
+		keys.into_iter().map(|key| self.get_with::<T, K>(key, options.clone())).collect()
+	}
+
 	/// Returns the key's entry in the cache.
 	///
 	/// The cache entry's `options` will be [merged][Options#merging-behavior] if the key already exists in the cache.
@@ -302,7 +579,142 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		K: Hash + Eq + ?Sized,
 		F::Key: Borrow<K> + for<'k> From<&'k K>
 	{
-		Persisted::<T, F, R>::new(&self.inner, self.inner.cache.get_or_create(key), Some(options)).get()
+		Persisted::<T, F, R>::new_for_frame(&self.inner, self.inner.cache.get_or_create(key), Some(options)).get()
+	}
+
+	/// Returns a [persisted cache slot][Persisted] for the given key, or an *empty* slot if `key` is `None`.
+	///
+	/// This is useful for conditional or dependent fetching, where the key can't be constructed until some other data
+	/// is available. An empty slot always returns a non-loading result with no data or error, and ignores
+	/// revalidations and mutations.
+	///
+	/// The cache entry's `options` will be [merged][Options#merging-behavior] if the key already exists in the cache.
+	pub fn persisted_when<T, K>(&self, key: Option<&K>, options: Options<F::Response<T>>) -> Persisted<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		let slot = key.map(|key| self.inner.cache.get_or_create(key)).unwrap_or_default();
+		Persisted::<T, F, R>::new(&self.inner, slot, Some(options))
+	}
+
+	/// Returns a [persisted cache slot][Persisted] for the given key, whose data is provided by a
+	/// [subscription][Subscriber] rather than one-off fetches.
+	///
+	/// The subscription is started when the slot is first [used][Persisted::get], and ends when the key's cache entry
+	/// is garbage collected.
+	///
+	/// The cache entry's `options` will be [merged][Options#merging-behavior] if the key already exists in the cache.
+	pub fn subscription<T, K>(&self, key: &K, options: Options<F::Response<T>>) -> Persisted<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F: Subscriber,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		let slot = self.inner.cache.get_or_create(key);
+		self.inner.cache.states().mutate(slot, |state| {
+			state.subscriber = Some(launch_subscription::<T, F, R>);
+		});
+		Persisted::<T, F, R>::new(&self.inner, slot, Some(options))
+	}
+
+	/// Returns a paginated query, where each page is its own entry in the cache.
+	///
+	/// `get_key` is called with the index of each page and the data of the previous page (`None` for the first page),
+	/// and returns the key for that page, or `None` if there are no more pages. Initially only the first page is
+	/// requested; use [`Infinite::load_more`] or [`Infinite::set_size`] to request more.
+	///
+	/// `options` are [merged][Options#merging-behavior] into every page's cache entry. [`Options::fallback`] only
+	/// applies to the first page.
+	///
+	/// Like [`SWR::persisted`], the returned [`Infinite`] is meant to be stored across renders.
+	pub fn infinite<T, G>(&self, options: Options<F::Response<T>>, get_key: G) -> Infinite<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		G: Fn(usize, Option<&F::Response<T>>) -> Option<F::Key> + Send + Sync + 'static
+	{
+		Infinite::new(&self.inner, options, get_key)
+	}
+
+	/// Returns a new [`MutationQueue`] for commands of type `C`.
+	///
+	/// Commands which fail to send are retried after `retry_interval`.
+	pub fn mutation_queue<C: Command<F>>(&self, retry_interval: Duration) -> MutationQueue<C, F, R> {
+		MutationQueue::new(&self.inner, retry_interval)
+	}
+
+	/// Returns a [persisted slot][Persisted] whose key only changes once it has gone unchanged for `delay`; see
+	/// [`Debounced`].
+	///
+	/// Like [`SWR::persisted`], the returned [`Debounced`] is meant to be stored across renders.
+	pub fn debounced<T>(&self, delay: Duration, options: Options<F::Response<T>>) -> Debounced<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static
+	{
+		Debounced::new(&self.inner, delay, options)
+	}
+
+	/// Returns the key's entry in the cache using the default [options][Options], or an empty, non-loading result if
+	/// `key` is `None`.
+	///
+	/// This is useful for conditional or dependent fetching, where the key can't be constructed until some other data
+	/// is available:
+	/// ```
+	/// # #[derive(serde::Deserialize)]
+	/// # struct User {
+	/// # 	id: u64
+	/// # }
+	/// # #[derive(serde::Deserialize)]
+	/// # struct Project;
+	/// # fn render<F: swr::Fetcher<Key = String, Response<User> = User>>(swr: &swr::SWR<F>) {
+	/// let user = swr.get::<User, _>("/me");
+	/// let projects =
+	/// 	swr.get_when::<Vec<Project>, _>(user.data.map(|user| format!("/users/{}/projects", user.id)).as_deref());
+	/// # }
+	/// ```
+	///
+	/// This should only be used during the GUI's rendering process.
+	pub fn get_when<T, K>(&self, key: Option<&K>) -> Result<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		match key {
+			Some(key) => self.get(key),
+			None => result::FetchResult::new_empty(CacheSlot::default(), Arc::downgrade(&self.inner))
+		}
+	}
+
+	/// Returns the key's entry in the cache, or an empty, non-loading result if `key` is `None`.
+	///
+	/// See [`SWR::get_when`] for more information.
+	pub fn get_with_when<T, K>(&self, key: Option<&K>, options: Options<F::Response<T>>) -> Result<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		match key {
+			Some(key) => self.get_with(key, options),
+			None => result::FetchResult::new_empty(CacheSlot::default(), Arc::downgrade(&self.inner))
+		}
+	}
+
+	/// Returns the entry in the cache for the key produced by `key`, or an empty, non-loading result if `key` returns
+	/// `None`.
+	///
+	/// See [`SWR::get_when`] for more information.
+	pub fn get_dependent<T, K, G>(&self, key: G, options: Options<F::Response<T>>) -> Result<T, F, R>
+	where
+		T: DeserializeOwned + Send + Sync + 'static,
+		K: Hash + Eq,
+		F::Key: Borrow<K> + for<'k> From<&'k K>,
+		G: FnOnce() -> Option<K>
+	{
+		self.get_with_when(key().as_ref(), options)
 	}
 
 	/// Returns this key's entry in the cache, or `None` if it does not exist.
@@ -334,6 +746,123 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		}
 	}
 
+	/// Triggers every key matching `predicate` to revalidate, returning the number of keys affected.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn revalidate_where<P>(&self, predicate: P) -> usize
+	where
+		P: FnMut(&F::Key) -> bool
+	{
+		let slots = self.inner.cache.slots_where(predicate);
+		let states = self.inner.cache.states();
+		let mut count = 0;
+		for state in slots.into_iter().filter_map(|slot| states.get(slot)) {
+			state.revalidate_intent().add(RevalidateIntent::MANUALLY_TRIGGERED);
+			count += 1;
+		}
+		if count > 0 {
+			self.inner.hook.request_redraw();
+		}
+		count
+	}
+
+	/// Replaces the data of every key matching `predicate` with the result of `mutator`, returning the number of keys
+	/// affected.
+	///
+	/// `mutator` is given the key and its current data, if present. Keys which contain data of a type other than `T`
+	/// are skipped. The cache is not locked while `mutator` runs, so it may freely use the cache itself.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn mutate_where<T, P, M>(&self, predicate: P, mut mutator: M) -> usize
+	where
+		T: Send + Sync + 'static,
+		P: FnMut(&F::Key) -> bool,
+		M: FnMut(&F::Key, Option<&Arc<F::Response<T>>>) -> Arc<F::Response<T>>
+	{
+		let slots = self.inner.cache.slots_where(predicate);
+		let snapshots: Vec<_> = {
+			let states = self.inner.cache.states();
+			slots
+				.into_iter()
+				.filter_map(|slot| {
+					let state = states.get(slot)?;
+					let data = match state.data::<T>() {
+						Some(Ok(data)) => Some(data),
+						Some(Err(_)) => return None,
+						None => None
+					};
+					Some((slot, state.key().clone(), data))
+				})
+				.collect()
+		};
+
+		let mutated: Vec<_> = snapshots
+			.into_iter()
+			.map(|(slot, key, data)| (slot, mutator(&key, data.as_ref())))
+			.collect();
+
+		let mut states = self.inner.cache.states();
+		let count = mutated
+			.into_iter()
+			.filter_map(|(slot, data)| {
+				states.mutate(slot, |state| {
+					let previous_data = state.insert(data);
+					state.record_history(previous_data);
+				})
+			})
+			.count();
+		drop(states);
+		if count > 0 {
+			self.inner.hook.request_redraw();
+		}
+		count
+	}
+
+	/// Drops the data of every key matching `predicate` and marks them as stale, returning the number of keys affected.
+	///
+	/// Invalidated keys are refetched the next time they are used; until then, they will be
+	/// [`loading`][Result::loading] rather than showing stale data.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn invalidate_where<P>(&self, predicate: P) -> usize
+	where
+		P: FnMut(&F::Key) -> bool
+	{
+		let slots = self.inner.cache.slots_where(predicate);
+		let mut states = self.inner.cache.states();
+		let count = slots
+			.into_iter()
+			.filter(|slot| states.mutate(*slot, |state| state.invalidate()).unwrap_or(false))
+			.count();
+		drop(states);
+		if count > 0 {
+			self.inner.hook.request_redraw();
+		}
+		count
+	}
+
+	/// Triggers every key in use which carries any of the given [tags][Options::tags] to revalidate, returning the
+	/// number of keys affected.
+	///
+	/// Keys which are not currently in use are unaffected; they will be revalidated when they are next used, as usual.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn invalidate_tags(&self, tags: &[&str]) -> usize {
+		let slots = self.inner.cache.slots_tagged(tags);
+		let states = self.inner.cache.states();
+		let mut count = 0;
+		for state in slots.into_iter().filter_map(|slot| states.get(slot)) {
+			if state.status().get(CacheEntryStatus::ALIVE, Ordering::Acquire) {
+				state.revalidate_intent().add(RevalidateIntent::MANUALLY_TRIGGERED);
+				count += 1;
+			}
+		}
+		if count > 0 {
+			self.inner.hook.request_redraw();
+		}
+		count
+	}
+
 	/// Replaces the key's entry in the cache with a successful result containing `data`, creating the entry if it
 	/// doesn't exist.
 	///
@@ -347,6 +876,70 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		self.inner.mutate(self.inner.cache.get_or_create(key), data);
 	}
 
+	/// Optimistically updates multiple keys at once, committing or rolling back the updates together depending on the
+	/// result of `mutator`.
+	///
+	/// `stage` is called immediately to stage updates with [`Transaction::optimistic`], which are then all applied to
+	/// the cache at once. The `mutator` is then run once with a reference to this cache's [`Fetcher`]; if it fails,
+	/// every key touched by the transaction is restored to the data it had before the transaction, unless a fetch or
+	/// another mutation has replaced its data in the meantime. The mutator's result is returned via a [runtime
+	/// `Task`][`runtime::Task`].
+	///
+	/// ```
+	/// # use std::{future::Future, pin::Pin, sync::Arc};
+	/// # struct Item;
+	/// # trait Api {
+	/// # 	fn move_item(&self, id: u64, from: &str, to: &str) -> Pin<Box<dyn Future<Output = Result<(), std::io::Error>> + Send>>;
+	/// # }
+	/// # fn move_item<F>(swr: &swr::SWR<F>, item_id: u64, list_a_without_item: Vec<Item>, list_b_with_item: Vec<Item>)
+	/// # where
+	/// # 	F: swr::Fetcher<Key = String, Response<Vec<Item>> = Vec<Item>> + Api
+	/// # {
+	/// swr.transaction(
+	/// 	|tx| {
+	/// 		tx.optimistic::<Vec<Item>, _>("/lists/a", Arc::new(list_a_without_item));
+	/// 		tx.optimistic::<Vec<Item>, _>("/lists/b", Arc::new(list_b_with_item));
+	/// 	},
+	/// 	move |fetcher| fetcher.move_item(item_id, "a", "b")
+	/// );
+	/// # }
+	/// ```
+	///
+	/// This function can be used outside of the GUI.
+	pub fn transaction<U, S, M, E, Fut>(&self, stage: S, mutator: M) -> R::Task<std::result::Result<U, E>>
+	where
+		U: Send,
+		S: FnOnce(&mut Transaction<'_, F, R>),
+		M: FnOnce(&F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		let mut transaction = Transaction::new(&self.inner);
+		stage(&mut transaction);
+		let Transaction { staged, revalidate, .. } = transaction;
+		self.inner.transaction(staged, revalidate, mutator)
+	}
+
+	/// Returns a [`Mutation`] handle which runs `mutator` each time it is [triggered][Mutation::trigger], populating
+	/// the key's data with the result.
+	///
+	/// The `mutator` is given the trigger's argument, the key's current data (if present), and a reference to this
+	/// cache's [`Fetcher`]. Like [`SWR::persisted`], the returned `Mutation` is meant to be stored across renders.
+	pub fn mutation<T, A, U, E, K, M, Fut>(&self, key: &K, mutator: M) -> Mutation<T, A, E, F, U, R>
+	where
+		T: Send + Sync + 'static,
+		A: Send + 'static,
+		U: Send + Sync + 'static,
+		E: Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>,
+		M: Fn(A, Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = std::result::Result<U, E>> + Send + 'static
+	{
+		let slot = self.inner.cache.get_or_create(key);
+		Mutation::new(&self.inner, slot, mutator)
+	}
+
 	/// Asynchronously mutates the cache entry with the given `key`, creating it if it doesn't exist.
 	///
 	/// The `mutator` is given the entry's current data (if present) and a reference to this cache's [`Fetcher`], and
@@ -367,6 +960,247 @@ impl<F: Fetcher, R: Runtime> SWR<F, R> {
 		let slot = self.inner.cache.get_or_create(key);
 		self.inner.mutate_with(slot, options, mutator)
 	}
+
+	/// Restores the data replaced by the key's most recent mutation, returning `false` if there is nothing to undo.
+	///
+	/// Keys only keep a history of their mutations if enabled with [`Options::history_depth`]. This only affects the
+	/// cache; use [`SWR::undo_with`] to also update the server.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn undo<K>(&self, key: &K) -> bool
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>
+	{
+		self.inner
+			.cache
+			.get(key)
+			.is_some_and(|slot| self.inner.step_history(slot, false, None).is_some())
+	}
+
+	/// Reapplies the key's most recently [undone][SWR::undo] mutation, returning `false` if there is nothing to redo.
+	///
+	/// Any new mutation of the key discards the mutations which could be redone. This only affects the cache; use
+	/// [`SWR::redo_with`] to also update the server.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn redo<K>(&self, key: &K) -> bool
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>
+	{
+		self.inner
+			.cache
+			.get(key)
+			.is_some_and(|slot| self.inner.step_history(slot, true, None).is_some())
+	}
+
+	/// Returns `true` if the key has a mutation which can be [undone][SWR::undo].
+	pub fn can_undo<K>(&self, key: &K) -> bool
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>
+	{
+		let Some(slot) = self.inner.cache.get(key) else {
+			return false;
+		};
+		let states = self.inner.cache.states();
+		states.get(slot).is_some_and(|state| state.can_undo())
+	}
+
+	/// Returns `true` if the key has a mutation which can be [redone][SWR::redo].
+	pub fn can_redo<K>(&self, key: &K) -> bool
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>
+	{
+		let Some(slot) = self.inner.cache.get(key) else {
+			return false;
+		};
+		let states = self.inner.cache.states();
+		states.get(slot).is_some_and(|state| state.can_redo())
+	}
+
+	/// [Undoes][SWR::undo] the key's most recent mutation, then runs a compensating `mutator` to update the server,
+	/// returning `None` if there is nothing to undo.
+	///
+	/// The `mutator` is given the restored data and a reference to this cache's [`Fetcher`], like with
+	/// [`SWR::mutate_with`]. If it fails and [`MutateOptions::rollback_on_error`] is set, the undo is reverted.
+	/// [`MutateOptions::optimistic_data`] is ignored, since the restored data is shown while the mutator runs.
+	///
+	/// ```
+	/// # use std::{future::Future, pin::Pin, sync::Arc};
+	/// # struct Document;
+	/// # trait Api {
+	/// # 	fn save_document(&self, document: &Document) -> Pin<Box<dyn Future<Output = Result<(), std::io::Error>> + Send>>;
+	/// # }
+	/// # fn undo<F: swr::Fetcher<Key = String, Response<Document> = Document> + Api>(swr: &swr::SWR<F>) {
+	/// swr.undo_with::<Document, _, _, _, _, _>(
+	/// 	"/documents/1",
+	/// 	swr::MutateOptions::default(),
+	/// 	|restored, fetcher| {
+	/// 		let restored = Arc::clone(restored.unwrap());
+	/// 		let save = fetcher.save_document(&restored);
+	/// 		async move { save.await.map(|_| restored) }
+	/// 	}
+	/// );
+	/// # }
+	/// ```
+	///
+	/// This function can be used outside of the GUI.
+	pub fn undo_with<T, U, K, M, E, Fut>(&self, key: &K, options: MutateOptions<F::Response<T>, U>, mutator: M) -> Option<R::Task<std::result::Result<U, E>>>
+	where
+		T: Send + Sync + 'static,
+		U: Send,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>,
+		M: FnOnce(Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		let slot = self.inner.cache.get(key)?;
+		self.inner.step_history_with(slot, false, options, mutator)
+	}
+
+	/// [Redoes][SWR::redo] the key's most recently undone mutation, then runs `mutator` to update the server, returning
+	/// `None` if there is nothing to redo.
+	///
+	/// See [`SWR::undo_with`] for details.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn redo_with<T, U, K, M, E, Fut>(&self, key: &K, options: MutateOptions<F::Response<T>, U>, mutator: M) -> Option<R::Task<std::result::Result<U, E>>>
+	where
+		T: Send + Sync + 'static,
+		U: Send,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K>,
+		M: FnOnce(Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + 'static,
+		E: Send,
+		Fut: Future<Output = std::result::Result<U, E>> + Send
+	{
+		let slot = self.inner.cache.get(key)?;
+		self.inner.step_history_with(slot, true, options, mutator)
+	}
+}
+
+#[cfg(feature = "persist")]
+#[cfg_attr(docsrs, doc(cfg(feature = "persist")))]
+impl<F: Fetcher, R: Runtime> SWR<F, R> {
+	/// Opts responses of type `T` in to being [persisted][SWR::persist] & [hydrated][SWR::hydrate].
+	///
+	/// `id` identifies the type in storage. It must be unique among the registered types, and should stay the same
+	/// across versions of the application (unless the type's serialized form changes), otherwise previously persisted
+	/// entries of this type will no longer be hydrated.
+	///
+	/// Types must be registered *before* calling [`SWR::hydrate`]; entries of unregistered types are skipped.
+	///
+	/// ```no_run
+	/// # #[derive(serde::Serialize, serde::Deserialize)]
+	/// # struct Profile;
+	/// # fn register<F: swr::Fetcher<Response<Profile> = Profile>>(swr: &swr::SWR<F>) {
+	/// swr.persist_type::<Profile>("profile");
+	/// # }
+	/// ```
+	pub fn persist_type<T>(&self, id: &'static str)
+	where
+		T: Send + Sync + 'static,
+		F::Response<T>: serde::Serialize + DeserializeOwned
+	{
+		self.inner
+			.codecs
+			.write()
+			.insert(TypeId::of::<F::Response<T>>(), persist::Codec::new::<F::Response<T>>(id));
+	}
+
+	/// Populates the cache with entries previously saved to `storage` by [`SWR::persist`], returning the number of
+	/// entries that were hydrated.
+	///
+	/// This should be called on startup, before the first frame is rendered, so that the last known data can be shown
+	/// immediately instead of a loading state. Hydrated entries are considered stale and will be revalidated when they
+	/// are first used.
+	///
+	/// Entries whose type was not registered with [`SWR::persist_type`], or which fail to deserialize, are skipped.
+	/// Keys which already have data in the cache are not overwritten.
+	pub fn hydrate<S: persist::Storage>(&self, storage: &S) -> std::result::Result<usize, S::Error>
+	where
+		F::Key: DeserializeOwned
+	{
+		let entries = storage.load()?;
+		let codecs: HashMap<&str, persist::Codec> = self.inner.codecs.read().values().map(|c| (c.id, *c)).collect();
+
+		let mut hydrated = 0;
+		for entry in entries {
+			let Some(codec) = codecs.get(entry.type_id.as_str()) else {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::warn!(type_id = entry.type_id, "skipping hydration of entry with unregistered type");
+				}
+				continue;
+			};
+			let Ok(key) = rmp_serde::from_slice::<F::Key>(&entry.key) else {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::warn!(type_id = entry.type_id, "failed to deserialize key of persisted entry");
+				}
+				continue;
+			};
+			let Some(data) = (codec.decode)(&entry.data) else {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::warn!(key = ?key, "failed to deserialize persisted entry");
+				}
+				continue;
+			};
+
+			let slot = self.inner.cache.get_or_create_owned(key);
+			let mut states = self.inner.cache.states();
+			states.mutate(slot, |state| {
+				if state.data_untyped().is_none() {
+					state.hydrate(
+						data,
+						#[cfg(debug_assertions)]
+						codec.type_name
+					);
+					hydrated += 1;
+				}
+			});
+		}
+
+		if hydrated > 0 {
+			self.inner.hook.request_redraw();
+		}
+		Ok(hydrated)
+	}
+
+	/// Writes all entries in the cache whose data type was registered with [`SWR::persist_type`] to `storage`,
+	/// returning the number of entries that were written.
+	///
+	/// This function can be used outside of the GUI, e.g. when the application is closing.
+	pub fn persist<S: persist::Storage>(&self, storage: &S) -> std::result::Result<usize, S::Error>
+	where
+		F::Key: serde::Serialize
+	{
+		let codecs = self.inner.codecs.read();
+		let entries: Vec<persist::StoredEntry> = {
+			let states = self.inner.cache.states();
+			states
+				.iter()
+				.filter_map(|(_, state)| {
+					let data = state.data_untyped()?;
+					let codec = codecs.get(&(*data.value).type_id())?;
+					Some(persist::StoredEntry {
+						key: rmp_serde::to_vec(state.key()).ok()?,
+						type_id: codec.id.to_string(),
+						data: (codec.encode)(&*data.value)?
+					})
+				})
+				.collect()
+		};
+		drop(codecs);
+
+		storage.store(&entries)?;
+		Ok(entries.len())
+	}
 }
 
 /// Creates a new SWR cache.
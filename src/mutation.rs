@@ -0,0 +1,282 @@
+use std::{
+	any::Any,
+	future::Future,
+	pin::Pin,
+	sync::{Arc, atomic::Ordering}
+};
+
+use crate::{
+	SWRInner,
+	cache::{CacheEntryData, CacheSlot},
+	fetcher::Fetcher,
+	options::MutateOptions,
+	runtime::{DefaultRuntime, Runtime, Task}
+};
+
+type MutatorFn<T, A, U, E, F> =
+	Arc<dyn Fn(A, Option<&Arc<<F as Fetcher>::Response<T>>>, &F) -> Pin<Box<dyn Future<Output = Result<U, E>> + Send>> + Send + Sync>;
+
+/// Configures how a [`Mutation`] handles being [triggered][Mutation::trigger] while a previous trigger is still in
+/// progress.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerPolicy {
+	/// New triggers are ignored while a mutation is in progress, e.g. to prevent a form from being submitted twice.
+	#[default]
+	Ignore,
+	/// Every trigger runs. The mutation's state reflects whichever trigger finished last.
+	Concurrent,
+	/// Every trigger runs, but only the outcome of the most recent trigger is reflected in the mutation's state; the
+	/// outcomes of earlier triggers which finish later are discarded.
+	Latest
+}
+
+/// The state of a key's [`Mutation`]s, stored in its cache entry.
+pub(crate) struct MutationState<R: Runtime> {
+	pub running: usize,
+	// incremented on each trigger, for `TriggerPolicy::Latest`
+	pub generation: u64,
+	// the data from before the oldest `TriggerPolicy::Latest` trigger in progress; later triggers roll back to & record
+	// history of this, rather than the optimistic data of an earlier trigger which will be discarded
+	pub latest_base: Option<Option<CacheEntryData>>,
+	// `Arc<U>` & `Arc<E>` of the mutation which last finished
+	pub data: Option<Arc<dyn Any + Send + Sync>>,
+	pub error: Option<Arc<dyn Any + Send + Sync>>,
+	// triggers in progress; held so they aren't cancelled on runtimes that cancel tasks when their handle is dropped
+	pub tasks: Vec<R::Task<()>>
+}
+
+impl<R: Runtime> Default for MutationState<R> {
+	fn default() -> Self {
+		Self {
+			running: 0,
+			generation: 0,
+			latest_base: None,
+			data: None,
+			error: None,
+			tasks: Vec::new()
+		}
+	}
+}
+
+/// A handle to a remote mutation of a key, created with [`SWR::mutation`][crate::SWR::mutation].
+///
+/// Unlike [`SWR::mutate_with`][crate::SWR::mutate_with], `Mutation` is meant to be stored across renders, and tracks
+/// the state of its most recent trigger, requesting a redraw when it changes:
+/// ```
+/// # struct Ui;
+/// # struct Response;
+/// # impl Ui {
+/// # 	fn button(&mut self, _: &str) -> Response { Response }
+/// # 	fn spinner(&mut self) {}
+/// # 	fn label(&mut self, _: String) {}
+/// # }
+/// # impl Response {
+/// # 	fn clicked(&self) -> bool { true }
+/// # }
+/// # #[derive(Clone)]
+/// # struct Form;
+/// # struct Editor<F: swr::Fetcher<Response<Form> = Form>> {
+/// # 	form: Form,
+/// # 	save: swr::Mutation<Form, Form, std::io::Error, F>
+/// # }
+/// # impl<F: swr::Fetcher<Response<Form> = Form>> Editor<F> {
+/// # fn render(&mut self, ui: &mut Ui) {
+/// if ui.button("Save").clicked() {
+/// 	self.save.trigger(self.form.clone());
+/// }
+/// if self.save.is_mutating() {
+/// 	ui.spinner();
+/// } else if let Some(error) = self.save.error() {
+/// 	ui.label(format!("failed to save: {error}"));
+/// }
+/// # }
+/// # }
+/// ```
+///
+/// The mutation's state is stored in the key's cache entry, so it is shared by all `Mutation`s of the same key. The
+/// cache entry will not be garbage collected for as long as the handle is held.
+///
+/// # Cloning
+/// `Mutation` is internally reference counted via [`Arc`], so it can be cheaply cloned.
+pub struct Mutation<T: Send + Sync + 'static, A, E, F: Fetcher, U = Arc<<F as Fetcher>::Response<T>>, R: Runtime = DefaultRuntime> {
+	slot: CacheSlot,
+	mutator: MutatorFn<T, A, U, E, F>,
+	policy: TriggerPolicy,
+	inner: Arc<SWRInner<F, R>>
+}
+
+impl<T, A, E, F, U, R> Clone for Mutation<T, A, E, F, U, R>
+where
+	T: Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	fn clone(&self) -> Self {
+		if let Some(state) = self.inner.cache.states().get(self.slot) {
+			state.strong_count.fetch_add(1, Ordering::Relaxed);
+		}
+		Self {
+			slot: self.slot,
+			mutator: Arc::clone(&self.mutator),
+			policy: self.policy,
+			inner: Arc::clone(&self.inner)
+		}
+	}
+}
+
+impl<T, A, E, F, U, R> Drop for Mutation<T, A, E, F, U, R>
+where
+	T: Send + Sync + 'static,
+	F: Fetcher,
+	R: Runtime
+{
+	fn drop(&mut self) {
+		let states = self.inner.cache.states();
+		let Some(state) = states.get(self.slot) else {
+			return;
+		};
+		state.strong_count.fetch_sub(1, Ordering::Release);
+	}
+}
+
+impl<T, A, E, F, U, R> Mutation<T, A, E, F, U, R>
+where
+	T: Send + Sync + 'static,
+	A: Send + 'static,
+	E: Send + Sync + 'static,
+	F: Fetcher,
+	U: Send + Sync + 'static,
+	R: Runtime
+{
+	pub(crate) fn new<M, Fut>(inner: &Arc<SWRInner<F, R>>, slot: CacheSlot, mutator: M) -> Self
+	where
+		M: Fn(A, Option<&Arc<F::Response<T>>>, &F) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<U, E>> + Send + 'static
+	{
+		if let Some(state) = inner.cache.states().get(slot) {
+			state.strong_count.fetch_add(1, Ordering::Relaxed);
+		}
+		Self {
+			slot,
+			mutator: Arc::new(move |arg, data, fetcher| Box::pin(mutator(arg, data, fetcher))),
+			policy: TriggerPolicy::default(),
+			inner: Arc::clone(inner)
+		}
+	}
+
+	/// Configures the [`TriggerPolicy`] using the builder pattern.
+	pub fn with_policy(mut self, policy: TriggerPolicy) -> Self {
+		self.policy = policy;
+		self
+	}
+
+	/// Runs the mutator with `arg`, populating the key's data with its result.
+	///
+	/// Returns `false` if the trigger was ignored due to the [`TriggerPolicy`].
+	///
+	/// This function can be used outside of the GUI.
+	pub fn trigger(&self, arg: A) -> bool
+	where
+		U: Into<Arc<F::Response<T>>> + Clone
+	{
+		self.trigger_with(arg, MutateOptions::default().with_populator(|data: &mut U, _| data.clone().into()))
+	}
+
+	/// Runs the mutator with `arg`, using `options` to control how the key's data is mutated.
+	///
+	/// Returns `false` if the trigger was ignored due to the [`TriggerPolicy`].
+	///
+	/// This function can be used outside of the GUI.
+	pub fn trigger_with(&self, arg: A, options: MutateOptions<F::Response<T>, U>) -> bool {
+		let inner = Arc::clone(&self.inner);
+		let slot = self.slot;
+		let mutator = Arc::clone(&self.mutator);
+		let policy = self.policy;
+
+		let mut states = self.inner.cache.states();
+		let triggered = states.mutate(self.slot, move |state| {
+			let mutation = &mut state.mutation;
+			mutation.tasks.retain(|task| !task.is_finished());
+			if policy == TriggerPolicy::Ignore && mutation.running > 0 {
+				return false;
+			}
+			mutation.running += 1;
+			mutation.generation += 1;
+			let generation = mutation.generation;
+
+			let task = inner.runtime.spawn({
+				let inner = Arc::clone(&inner);
+				async move {
+					// an earlier trigger must not overwrite the data of a later one
+					let latest_generation = (policy == TriggerPolicy::Latest).then_some(generation);
+					let res = Arc::clone(&inner)
+						.run_mutation(slot, options, latest_generation, move |data, fetcher| mutator(arg, data, fetcher))
+						.await;
+
+					let mut states = inner.cache.states();
+					states.mutate(slot, |state| {
+						let mutation = &mut state.mutation;
+						mutation.running -= 1;
+						if latest_generation.is_none_or(|generation| generation == mutation.generation) {
+							match res {
+								Ok(data) => {
+									mutation.data = Some(Arc::new(data));
+									mutation.error = None;
+								}
+								Err(err) => mutation.error = Some(Arc::new(err))
+							}
+						}
+					});
+					inner.hook.request_redraw();
+				}
+			});
+			state.mutation.tasks.push(task);
+			true
+		});
+		drop(states);
+
+		if triggered == Some(true) {
+			self.inner.hook.request_redraw();
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Returns `true` if a trigger is in progress.
+	pub fn is_mutating(&self) -> bool {
+		self.inner.cache.states().get(self.slot).is_some_and(|state| state.mutation.running > 0)
+	}
+
+	/// Returns the result of the most recent successful trigger, or `None` if no trigger has succeeded since the last
+	/// [reset][Mutation::reset].
+	///
+	/// Returns `None` if the most recent trigger was of a `Mutation` of the same key with a different result type.
+	pub fn data(&self) -> Option<Arc<U>> {
+		let states = self.inner.cache.states();
+		let data = Arc::clone(states.get(self.slot)?.mutation.data.as_ref()?);
+		data.downcast().ok()
+	}
+
+	/// Returns the error of the most recent trigger, or `None` if it succeeded.
+	///
+	/// Returns `None` if the most recent trigger was of a `Mutation` of the same key with a different error type.
+	pub fn error(&self) -> Option<Arc<E>> {
+		let states = self.inner.cache.states();
+		let error = Arc::clone(states.get(self.slot)?.mutation.error.as_ref()?);
+		error.downcast().ok()
+	}
+
+	/// Clears the mutation's [data][Mutation::data] & [error][Mutation::error].
+	///
+	/// Triggers in progress are unaffected, and will still update the mutation's state once they finish.
+	pub fn reset(&self) {
+		let mut states = self.inner.cache.states();
+		states.mutate(self.slot, |state| {
+			state.mutation.data = None;
+			state.mutation.error = None;
+		});
+		drop(states);
+		self.inner.hook.request_redraw();
+	}
+}
@@ -1,16 +1,23 @@
 use std::{
+	any::{Any, TypeId},
+	error::Error,
+	fmt,
 	num::{NonZeroU8, NonZeroU32},
 	sync::Arc,
 	time::Duration
 };
 
+use crate::{fetcher::Tag, util::random_u64};
+
 /// # Merging behavior
 /// When a key is retrieved multiple times using [`Options`], the actual options used by the cache entry will be
 /// *merged*. Merging wil **OR** boolean options like [`Options::revalidate_on_focus`] and choose the **minimum**
-/// for other options like [`Options::refresh_interval`].
+/// for other options like [`Options::refresh_interval`]. [`Options::tags`] are combined, and the
+/// [`Options::callbacks`] of every usage are invoked. The first [`Options::error_retry_policy`] other than
+/// [`RetryPolicy::Constant`] is kept.
 ///
-/// Note that [`Options::fallback`] operates independently of the cache and thus is *local to each retrieved key*; it
-/// does not apply to other usages of the key that do not specify their own fallback.
+/// Note that [`Options::fallback`] and [`Options::enabled`] operate independently of the cache and thus are *local to
+/// each retrieved key*; they do not apply to other usages of the key that do not specify their own options.
 ///
 /// Imagine that two parts of your code use the same key, but with different options:
 /// - **Options A** specifies a fallback, refreshes every 5 seconds, but does not revalidate on focus.
@@ -23,6 +30,12 @@ use std::{
 pub struct Options<T: Send + Sync + 'static> {
 	/// Initial data to return until the cache is populated by a fetch.
 	pub fallback: Option<Arc<T>>,
+	/// Whether or not this usage of the key may trigger fetches automatically, i.e. on first use, when data is stale,
+	/// or when the application is focused.
+	///
+	/// Disabled keys can still be revalidated manually and mutated. If a disabled key is enabled and has never been
+	/// fetched, it will be fetched as if it were used for the first time.
+	pub enabled: bool,
 	/// Whether or not to perform a fetch when this key is used for the first time.
 	pub fetch_on_first_use: bool,
 	/// The length of time it takes after this key falls out of use for its entry to be garbage collected.
@@ -46,17 +59,69 @@ pub struct Options<T: Send + Sync + 'static> {
 	/// If `refresh_interval` is `None`, this option does nothing.
 	pub refresh_when_unfocused: bool,
 	/// An optional interval at which to retry fetches if an error occurs.
+	///
+	/// This is the base delay used by the [retry policy][Options::error_retry_policy].
 	pub error_retry_interval: Option<Duration>,
 	/// The maximum amount of times to retry fetching if an error occurs.
 	pub error_retry_count: Option<NonZeroU8>,
+	/// How the delay between retries changes with each failed attempt.
+	///
+	/// When merging, the first policy other than [`RetryPolicy::Constant`] that the key is used with takes precedence;
+	/// later usages with a different policy do not replace it.
+	pub error_retry_policy: RetryPolicy,
+	/// An optional upper bound on the delay between retries.
+	pub error_retry_max_interval: Option<Duration>,
 	/// An optional amount of time to throttle between requests.
-	pub throttle: Option<Duration>
+	pub throttle: Option<Duration>,
+	/// An optional window after a successful fetch during which automatic revalidations (on first use, on focus, or
+	/// when data is stale) are deduplicated, i.e. dropped.
+	///
+	/// This prevents, for example, multiple widgets using the same key from each triggering a fetch as they come into
+	/// view. Manual revalidations are never deduplicated.
+	///
+	/// Deduplication is opt-in; this defaults to `None`.
+	pub dedupe_interval: Option<Duration>,
+	/// An optional amount of time after a successful fetch during which the key's data is considered *fresh*.
+	///
+	/// Fresh data is not automatically revalidated when the key comes back into use, when the application is focused,
+	/// or when it is marked stale; manual revalidations, refreshes and retries still occur. Once the stale time
+	/// elapses, or if it is `None` (the default), data is considered stale and is revalidated as usual.
+	///
+	/// See also [`FetchResult::is_stale`][crate::Result::is_stale].
+	pub stale_time: Option<Duration>,
+	/// Tags to attach to this key's cache entry, allowing it to be revalidated along with other entries sharing the
+	/// same tag via [`SWR::invalidate_tags`][crate::SWR::invalidate_tags].
+	///
+	/// The [`Fetcher`][crate::Fetcher] may attach more tags based on the response via
+	/// [`Fetcher::tags`][crate::Fetcher::tags].
+	pub tags: Vec<Tag>,
+	/// Callbacks invoked when a fetch of this key succeeds, fails, or is [slow][Options::loading_timeout].
+	///
+	/// See [`Options::with_on_success`], [`Options::with_on_error`], and [`Options::with_on_loading_slow`].
+	pub callbacks: Callbacks<T>,
+	/// The amount of time after which an initial fetch is considered slow, triggering the
+	/// [`on_loading_slow`][Options::with_on_loading_slow] callbacks.
+	///
+	/// Defaults to `None`, i.e. fetches are never considered slow.
+	pub loading_timeout: Option<Duration>,
+	/// The maximum number of mutations of this key which can be [undone][crate::SWR::undo], or `None` (the default)
+	/// to not keep a history.
+	///
+	/// Mutations via [`SWR::mutate`][crate::SWR::mutate], [`SWR::mutate_with`][crate::SWR::mutate_with],
+	/// [`SWR::mutate_where`][crate::SWR::mutate_where], [`SWR::transaction`][crate::SWR::transaction],
+	/// [`Mutation`][crate::Mutation], and [`MutationQueue::push`][crate::MutationQueue::push] are recorded; fetches are
+	/// not.
+	/// Fetched data discards the key's history instead, since the data it would restore may be outdated by then.
+	///
+	/// When merging, the **maximum** depth is used.
+	pub history_depth: Option<NonZeroU8>
 }
 
 impl<T: Send + Sync + 'static> Default for Options<T> {
 	fn default() -> Self {
 		Self {
 			fallback: None,
+			enabled: true,
 			fetch_on_first_use: true,
 			garbage_collect_timeout: Some(Duration::from_secs(600)),
 			revalidate_on_focus: true,
@@ -65,7 +130,15 @@ impl<T: Send + Sync + 'static> Default for Options<T> {
 			refresh_when_unfocused: false,
 			error_retry_interval: Some(Duration::from_secs(5)),
 			error_retry_count: Some(NonZeroU8::new(5).unwrap()),
-			throttle: Some(Duration::from_secs(2))
+			error_retry_policy: RetryPolicy::Constant,
+			error_retry_max_interval: None,
+			throttle: Some(Duration::from_secs(2)),
+			dedupe_interval: None,
+			stale_time: None,
+			tags: Vec::new(),
+			callbacks: Callbacks::default(),
+			loading_timeout: None,
+			history_depth: None
 		}
 	}
 }
@@ -84,6 +157,204 @@ impl<T: Send + Sync + 'static> Options<T> {
 			..Options::default()
 		}
 	}
+
+	/// Registers a callback to be invoked with the key & new data after a fetch succeeds, using the builder pattern.
+	///
+	/// The callback is registered for as long as this usage of the key is alive; see [`Callbacks`].
+	///
+	/// # Panics
+	/// `K` must be the [`Fetcher::Key`][crate::Fetcher::Key]. Using these options with a cache whose key type differs,
+	/// or registering callbacks with different key types, panics.
+	pub fn with_on_success<K: 'static>(mut self, callback: impl Fn(&K, &Arc<T>) + Send + Sync + 'static) -> Self {
+		self.callbacks.set_key_type::<K>();
+		self.callbacks
+			.on_success
+			.push(Arc::new(move |key, data| callback(downcast_key(key), data)));
+		self
+	}
+
+	/// Registers a callback to be invoked with the key & error after a fetch fails, using the builder pattern.
+	///
+	/// The error is the [`Fetcher::Error`][crate::Fetcher::Error], and can be downcast to inspect it.
+	///
+	/// # Panics
+	/// `K` must be the [`Fetcher::Key`][crate::Fetcher::Key]; see [`Options::with_on_success`].
+	pub fn with_on_error<K: 'static>(mut self, callback: impl Fn(&K, &(dyn Error + 'static)) + Send + Sync + 'static) -> Self {
+		self.callbacks.set_key_type::<K>();
+		self.callbacks
+			.on_error
+			.push(Arc::new(move |key, error| callback(downcast_key(key), error)));
+		self
+	}
+
+	/// Registers a callback to be invoked with the key when its initial fetch takes longer than
+	/// [`Options::loading_timeout`], using the builder pattern.
+	///
+	/// # Panics
+	/// `K` must be the [`Fetcher::Key`][crate::Fetcher::Key]; see [`Options::with_on_success`].
+	pub fn with_on_loading_slow<K: 'static>(mut self, callback: impl Fn(&K) + Send + Sync + 'static) -> Self {
+		self.callbacks.set_key_type::<K>();
+		self.callbacks.on_loading_slow.push(Arc::new(move |key| callback(downcast_key(key))));
+		self
+	}
+}
+
+// Keys are passed as `&dyn Any` since `Options` isn't generic over the fetcher; their type is checked when the
+// callbacks are registered to a cache entry, see `Callbacks::check_key_type`.
+pub(crate) type SuccessCallback<T> = Arc<dyn Fn(&dyn Any, &Arc<T>) + Send + Sync>;
+pub(crate) type ErrorCallback = Arc<dyn Fn(&dyn Any, &(dyn Error + 'static)) + Send + Sync>;
+pub(crate) type LoadingSlowCallback = Arc<dyn Fn(&dyn Any) + Send + Sync>;
+
+/// Lifecycle callbacks registered via [`Options::with_on_success`], [`Options::with_on_error`], and
+/// [`Options::with_on_loading_slow`].
+///
+/// Callbacks are invoked from the fetch task, after the cache has been updated.
+///
+/// When merging, the callbacks of every live usage of a key are invoked. Callbacks passed to
+/// [`SWR::get_with`][crate::SWR::get_with] and friends belong to the frame they were registered in, and are replaced by
+/// those registered in the next frame; callbacks of a [`Persisted`][crate::Persisted] slot are registered until the
+/// slot is dropped or [re-pointed][crate::Persisted::set_key].
+pub struct Callbacks<T> {
+	// the type of key the callbacks accept, and its name for panic messages
+	key_type: Option<(TypeId, &'static str)>,
+	pub(crate) on_success: Vec<SuccessCallback<T>>,
+	pub(crate) on_error: Vec<ErrorCallback>,
+	pub(crate) on_loading_slow: Vec<LoadingSlowCallback>
+}
+
+impl<T> Callbacks<T> {
+	fn is_empty(&self) -> bool {
+		self.on_success.is_empty() && self.on_error.is_empty() && self.on_loading_slow.is_empty()
+	}
+
+	fn set_key_type<K: 'static>(&mut self) {
+		match self.key_type {
+			Some((type_id, type_name)) => assert!(
+				type_id == TypeId::of::<K>(),
+				"callbacks accepting keys of type `{type_name}` and `{}` were registered to the same options",
+				std::any::type_name::<K>()
+			),
+			None => self.key_type = Some((TypeId::of::<K>(), std::any::type_name::<K>()))
+		}
+	}
+
+	/// Asserts that the callbacks accept keys of type `K`, i.e. the [`Fetcher::Key`][crate::Fetcher::Key] of the cache
+	/// they're registered to.
+	pub(crate) fn check_key_type<K: 'static>(&self) {
+		if let Some((type_id, type_name)) = self.key_type {
+			assert!(
+				type_id == TypeId::of::<K>(),
+				"callbacks accepting keys of type `{type_name}` were registered to a cache whose keys are `{}`",
+				std::any::type_name::<K>()
+			);
+		}
+	}
+}
+
+fn downcast_key<K: 'static>(key: &dyn Any) -> &K {
+	key.downcast_ref().expect("key type should have been checked when registering callbacks")
+}
+
+impl<T> Default for Callbacks<T> {
+	fn default() -> Self {
+		Self {
+			key_type: None,
+			on_success: Vec::new(),
+			on_error: Vec::new(),
+			on_loading_slow: Vec::new()
+		}
+	}
+}
+
+impl<T> Clone for Callbacks<T> {
+	fn clone(&self) -> Self {
+		Self {
+			key_type: self.key_type,
+			on_success: self.on_success.clone(),
+			on_error: self.on_error.clone(),
+			on_loading_slow: self.on_loading_slow.clone()
+		}
+	}
+}
+
+impl<T> fmt::Debug for Callbacks<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Callbacks")
+			.field("on_success", &self.on_success.len())
+			.field("on_error", &self.on_error.len())
+			.field("on_loading_slow", &self.on_loading_slow.len())
+			.finish()
+	}
+}
+
+/// A usage of a cache entry which may register [`Callbacks`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Usage {
+	/// A usage which only lasts for the current frame, i.e. [`SWR::get_with`][crate::SWR::get_with].
+	Frame,
+	/// A [`Persisted`][crate::Persisted] slot, identified by a unique ID.
+	Persisted(u64)
+}
+
+/// Determines the delay between retries when a fetch fails.
+///
+/// Each policy is driven by the number of the failed attempt (starting at `0`) and the base
+/// [`Options::error_retry_interval`], and is capped by [`Options::error_retry_max_interval`].
+#[derive(Clone, Default)]
+pub enum RetryPolicy {
+	/// Wait the same amount of time between each attempt.
+	#[default]
+	Constant,
+	/// Double the delay with each attempt.
+	Exponential,
+	/// Pick a random delay between the base interval and 3x the previous delay. This avoids many clients retrying in
+	/// lockstep after an outage.
+	///
+	/// See [*Exponential Backoff And Jitter*](https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/).
+	DecorrelatedJitter,
+	/// Use a [custom policy][CustomRetryPolicy], which can inspect the error to decide whether to retry at all.
+	Custom(Arc<dyn CustomRetryPolicy>)
+}
+
+impl fmt::Debug for RetryPolicy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Constant => f.write_str("Constant"),
+			Self::Exponential => f.write_str("Exponential"),
+			Self::DecorrelatedJitter => f.write_str("DecorrelatedJitter"),
+			Self::Custom(_) => f.write_str("Custom(..)")
+		}
+	}
+}
+
+/// A user-defined [`RetryPolicy`].
+///
+/// This is implemented for closures, so you can write:
+/// ```
+/// # use std::{error::Error, sync::Arc, time::Duration};
+/// let policy = swr::RetryPolicy::Custom(Arc::new(|attempt: u8, error: &(dyn Error + 'static)| {
+/// 	if error.is::<std::fmt::Error>() {
+/// 		// not retryable
+/// 		return None;
+/// 	}
+/// 	Some(Duration::from_secs(1 << attempt))
+/// }));
+/// ```
+pub trait CustomRetryPolicy: Send + Sync {
+	/// Returns the delay before the next retry after `attempt` (starting at `0`) failed with `error`, or `None` to stop
+	/// retrying.
+	///
+	/// `error` is the [`Fetcher::Error`][crate::Fetcher::Error], and can be downcast to inspect it.
+	fn retry_delay(&self, attempt: u8, error: &(dyn Error + 'static)) -> Option<Duration>;
+}
+
+impl<F> CustomRetryPolicy for F
+where
+	F: Fn(u8, &(dyn Error + 'static)) -> Option<Duration> + Send + Sync
+{
+	fn retry_delay(&self, attempt: u8, error: &(dyn Error + 'static)) -> Option<Duration> {
+		self(attempt, error)
+	}
 }
 
 #[derive(Debug, Default)]
@@ -105,12 +376,24 @@ impl RevalidateFlags {
 pub(crate) struct StoredOptions {
 	pub revalidate_flags: RevalidateFlags,
 	pub error_retry_count: Option<NonZeroU8>,
+	pub history_depth: Option<NonZeroU8>,
+	error_retry_policy: RetryPolicy,
+	// tags attached by usages of the entry, as opposed to those attached by the fetcher
+	tags: Vec<Tag>,
+	// type-erased `Callbacks<T>` of each usage of the entry which registered any
+	callbacks: Vec<(Usage, Arc<dyn Any + Send + Sync>)>,
+	// whether `Usage::Frame` callbacks were registered during the current frame
+	frame_callbacks_registered: bool,
 	// `Duration` is 16 bytes and we definitely don't require sub-millisecond precision
 	garbage_collect_timeout_ms: Option<NonZeroU32>,
 	focus_throttle_interval_ms: Option<NonZeroU32>,
 	refresh_interval_ms: Option<NonZeroU32>,
 	error_retry_interval_ms: Option<NonZeroU32>,
-	throttle_ms: Option<NonZeroU32>
+	error_retry_max_interval_ms: Option<NonZeroU32>,
+	throttle_ms: Option<NonZeroU32>,
+	dedupe_interval_ms: Option<NonZeroU32>,
+	stale_time_ms: Option<NonZeroU32>,
+	loading_timeout_ms: Option<NonZeroU32>
 }
 
 impl Default for StoredOptions {
@@ -118,14 +401,23 @@ impl Default for StoredOptions {
 		let mut options = StoredOptions {
 			revalidate_flags: RevalidateFlags(0),
 			error_retry_count: None,
+			history_depth: None,
+			error_retry_policy: RetryPolicy::Constant,
+			tags: Vec::new(),
+			callbacks: Vec::new(),
+			frame_callbacks_registered: false,
 			garbage_collect_timeout_ms: None,
 			focus_throttle_interval_ms: None,
 			refresh_interval_ms: None,
 			error_retry_interval_ms: None,
-			throttle_ms: None
+			error_retry_max_interval_ms: None,
+			throttle_ms: None,
+			dedupe_interval_ms: None,
+			stale_time_ms: None,
+			loading_timeout_ms: None
 		};
 		// Inherit our options from the default values for `Options`
-		options.update_from_inner(&Options::default());
+		options.update_from(&Options::<()>::default());
 		options
 	}
 }
@@ -143,19 +435,71 @@ impl StoredOptions {
 	pub(crate) fn error_retry_interval(&self) -> Option<Duration> {
 		self.error_retry_interval_ms.map(|d| Duration::from_millis(d.get() as _))
 	}
+	pub(crate) fn error_retry_max_interval(&self) -> Option<Duration> {
+		self.error_retry_max_interval_ms.map(|d| Duration::from_millis(d.get() as _))
+	}
 	pub(crate) fn throttle(&self) -> Option<Duration> {
 		self.throttle_ms.map(|d| Duration::from_millis(d.get() as _))
 	}
+	pub(crate) fn dedupe_interval(&self) -> Option<Duration> {
+		self.dedupe_interval_ms.map(|d| Duration::from_millis(d.get() as _))
+	}
+	pub(crate) fn stale_time(&self) -> Option<Duration> {
+		self.stale_time_ms.map(|d| Duration::from_millis(d.get() as _))
+	}
+	pub(crate) fn loading_timeout(&self) -> Option<Duration> {
+		self.loading_timeout_ms.map(|d| Duration::from_millis(d.get() as _))
+	}
 
-	#[inline(always)]
-	pub(crate) fn update_from<T: Send + Sync + 'static>(&mut self, options: &Options<T>) {
-		// Save a bit on codegen by not specializing `update_from` for every variant of `T`.
-		// transmuting from Options<T> to Options<()> is safe because the fallback field which uses T is an Arc (always usize
-		// regardless of T), and we don't touch it
-		self.update_from_inner(unsafe { std::mem::transmute::<&Options<T>, &Options<()>>(options) });
+	/// Adds the tags of a usage of the entry, returning those which weren't already present.
+	pub(crate) fn add_tags(&mut self, tags: &[Tag]) -> Vec<Tag> {
+		let added: Vec<Tag> = tags.iter().filter(|tag| !self.tags.contains(tag)).cloned().collect();
+		self.tags.extend(added.iter().cloned());
+		added
+	}
+
+	pub(crate) fn has_tag(&self, tag: &Tag) -> bool {
+		self.tags.contains(tag)
+	}
+
+	/// Registers the callbacks of `usage`, replacing those it previously registered.
+	///
+	/// Frame usages are only replaced by the first registration of each frame, so that all usages within a frame are
+	/// kept.
+	pub(crate) fn register_callbacks<T: Send + Sync + 'static>(&mut self, usage: Usage, callbacks: &Callbacks<T>) {
+		match usage {
+			Usage::Frame if self.frame_callbacks_registered => {}
+			Usage::Frame => {
+				self.frame_callbacks_registered = true;
+				self.unregister_callbacks(usage);
+			}
+			Usage::Persisted(_) => self.unregister_callbacks(usage)
+		}
+		if !callbacks.is_empty() {
+			self.callbacks.push((usage, Arc::new(callbacks.clone())));
+		}
+	}
+
+	pub(crate) fn unregister_callbacks(&mut self, usage: Usage) {
+		self.callbacks.retain(|(u, _)| *u != usage);
 	}
 
-	fn update_from_inner(&mut self, options: &Options<()>) {
+	/// Drops the callbacks of frame usages if the entry was not used with callbacks during the frame that just ended.
+	pub(crate) fn end_frame(&mut self) {
+		if !std::mem::take(&mut self.frame_callbacks_registered) {
+			self.unregister_callbacks(Usage::Frame);
+		}
+	}
+
+	/// Returns the callbacks of every usage of the entry.
+	pub(crate) fn callbacks<T: Send + Sync + 'static>(&self) -> impl Iterator<Item = &Callbacks<T>> {
+		// usages of a key always share the same response type, barring a type mismatch, which is reported elsewhere
+		self.callbacks
+			.iter()
+			.filter_map(|(_, callbacks)| callbacks.downcast_ref::<Callbacks<T>>())
+	}
+
+	pub(crate) fn update_from<T: Send + Sync + 'static>(&mut self, options: &Options<T>) {
 		if options.fetch_on_first_use {
 			self.revalidate_flags.set(RevalidateFlags::ON_FIRST_USE);
 		}
@@ -170,7 +514,42 @@ impl StoredOptions {
 		}
 		self.error_retry_interval_ms = merge_min(self.error_retry_interval_ms, duration_as_optional_millis(&options.error_retry_interval));
 		self.error_retry_count = merge_min(self.error_retry_count, options.error_retry_count);
+		// the first non-constant policy wins, so that usages can't keep replacing each other's policies
+		if matches!(self.error_retry_policy, RetryPolicy::Constant) {
+			self.error_retry_policy = options.error_retry_policy.clone();
+		}
+		self.error_retry_max_interval_ms = merge_min(self.error_retry_max_interval_ms, duration_as_optional_millis(&options.error_retry_max_interval));
 		self.throttle_ms = merge_min(self.throttle_ms, duration_as_optional_millis(&options.throttle));
+		self.dedupe_interval_ms = merge_min(self.dedupe_interval_ms, duration_as_optional_millis(&options.dedupe_interval));
+		self.stale_time_ms = merge_min(self.stale_time_ms, duration_as_optional_millis(&options.stale_time));
+		self.loading_timeout_ms = merge_min(self.loading_timeout_ms, duration_as_optional_millis(&options.loading_timeout));
+		self.history_depth = self.history_depth.max(options.history_depth);
+	}
+
+	/// Returns the delay before retrying after `attempt` failed with `error`, or `None` if the fetch should not be
+	/// retried. `previous_delay` is the delay before the failed attempt, if it was a retry.
+	pub(crate) fn retry_delay(&self, attempt: u8, error: &(dyn Error + 'static), previous_delay: Option<Duration>) -> Option<Duration> {
+		if let Some(max_count) = self.error_retry_count {
+			if attempt >= max_count.get() {
+				return None;
+			}
+		}
+
+		let delay = match &self.error_retry_policy {
+			RetryPolicy::Constant => self.error_retry_interval()?,
+			RetryPolicy::Exponential => self.error_retry_interval()?.saturating_mul(2u32.saturating_pow(attempt.into())),
+			RetryPolicy::DecorrelatedJitter => {
+				let base = self.error_retry_interval()?;
+				let upper = previous_delay.unwrap_or(base).saturating_mul(3).max(base);
+				let range_ms = (upper - base).as_millis() as u64;
+				base + Duration::from_millis(random_u64() % (range_ms + 1))
+			}
+			RetryPolicy::Custom(policy) => policy.retry_delay(attempt, error)?
+		};
+		Some(match self.error_retry_max_interval() {
+			Some(max) => delay.min(max),
+			None => delay
+		})
 	}
 }
 
@@ -202,6 +581,9 @@ pub struct MutateOptions<T: Send + Sync + 'static, U = T> {
 	pub rollback_on_error: bool,
 	/// Whether or not the key should be revalidated after the action is complete.
 	pub revalidate: bool,
+	/// Whether or not the data replaced by this mutation is recorded in the key's [history][Options::history_depth],
+	/// allowing the mutation to be [undone][crate::SWR::undo]. Defaults to `true`.
+	pub record_history: bool,
 	/// A function used to map from the action's result to the actual stored key data.
 	///
 	/// The function accepts a mutable reference to the action's result, and the previous (non-optimistic) data stored
@@ -236,6 +618,7 @@ pub struct MutateOptions<T: Send + Sync + 'static, U = T> {
 	/// 	|_data, _fetcher| async move { Ok::<_, usize>(TransactionResult { balance: 42 }) }
 	/// );
 	/// ```
+	#[allow(clippy::type_complexity)]
 	pub populator: Box<dyn Fn(&mut U, Option<&Arc<T>>) -> Arc<T> + Send>
 }
 
@@ -245,6 +628,7 @@ impl<T: Send + Sync + 'static> Default for MutateOptions<T, Arc<T>> {
 			optimistic_data: None,
 			rollback_on_error: true,
 			revalidate: false,
+			record_history: true,
 			populator: Box::new(|x, _prev| x.clone())
 		}
 	}
@@ -257,6 +641,7 @@ impl<T: Send + Sync + 'static, U> MutateOptions<T, U> {
 			optimistic_data: self.optimistic_data,
 			rollback_on_error: self.rollback_on_error,
 			revalidate: self.revalidate,
+			record_history: self.record_history,
 			populator: Box::new(populator)
 		}
 	}
@@ -0,0 +1,119 @@
+//! Provides the [`Storage`] trait, used to persist the cache across application restarts.
+//!
+//! Persistence is opt-in per response type: only types registered with [`SWR::persist_type`] are written to storage
+//! by [`SWR::persist`] and read back by [`SWR::hydrate`]. Entries are encoded with MessagePack via [`rmp_serde`], and
+//! tagged with the identifier their type was registered with.
+//!
+//! [`MutationQueue`]s can also persist their pending commands to a `Storage` of [`StoredCommand`]s; see
+//! [`MutationQueue::persist`].
+//!
+//! SWR provides the following `Storage` implementations:
+//! - **[`FileStorage`]** - stores the cache in a single file (available with the `fs` Cargo feature)
+//! - *write your own by implementing [`Storage`]!*
+//!
+//! [`SWR::persist_type`]: crate::SWR::persist_type
+//! [`SWR::persist`]: crate::SWR::persist
+//! [`SWR::hydrate`]: crate::SWR::hydrate
+//! [`MutationQueue`]: crate::MutationQueue
+//! [`MutationQueue::persist`]: crate::MutationQueue::persist
+
+use std::{any::Any, fmt, sync::Arc};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+#[cfg(feature = "fs")]
+mod fs;
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub use self::fs::FileStorage;
+
+/// A single serialized cache entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredEntry {
+	/// The entry's key, encoded with MessagePack.
+	pub key: Vec<u8>,
+	/// The identifier the entry's response type was [registered][crate::SWR::persist_type] with, used to find the
+	/// correct decoder when hydrating.
+	pub type_id: String,
+	/// The entry's data, encoded with MessagePack.
+	pub data: Vec<u8>
+}
+
+/// A single serialized [command][crate::Command] of a [`MutationQueue`][crate::MutationQueue].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredCommand {
+	/// The command, encoded with MessagePack.
+	pub data: Vec<u8>
+}
+
+/// A backend capable of saving and loading serialized records; cache entries ([`StoredEntry`]) by default, or
+/// [`StoredCommand`]s.
+pub trait Storage<Record = StoredEntry>: Send + Sync {
+	/// The error type returned when reading or writing fails.
+	type Error;
+
+	/// Loads all records previously written by [`Storage::store`].
+	///
+	/// If nothing has been stored yet, this should return an empty `Vec` rather than an error.
+	fn load(&self) -> Result<Vec<Record>, Self::Error>;
+
+	/// Stores the given records, replacing any previously stored records.
+	fn store(&self, records: &[Record]) -> Result<(), Self::Error>;
+}
+
+/// An error which occurred while [persisting commands][crate::MutationQueue::persist].
+#[derive(Debug)]
+pub enum PersistError<E> {
+	/// A command could not be serialized.
+	Encode(rmp_serde::encode::Error),
+	/// The storage failed to store the serialized commands.
+	Storage(E)
+}
+
+impl<E: fmt::Display> fmt::Display for PersistError<E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Encode(e) => {
+				f.write_str("Failed to serialize command: ")?;
+				fmt::Display::fmt(e, f)
+			}
+			Self::Storage(e) => {
+				f.write_str("Failed to store commands: ")?;
+				fmt::Display::fmt(e, f)
+			}
+		}
+	}
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for PersistError<E> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Encode(e) => Some(e),
+			Self::Storage(e) => Some(e)
+		}
+	}
+}
+
+pub(crate) type UntypedData = Arc<dyn Any + Send + Sync>;
+
+/// Type-erased serializer & deserializer for a response type registered with `SWR::persist_type`.
+#[derive(Clone, Copy)]
+pub(crate) struct Codec {
+	pub id: &'static str,
+	#[cfg(debug_assertions)]
+	pub type_name: &'static str,
+	pub encode: fn(&(dyn Any + Send + Sync)) -> Option<Vec<u8>>,
+	pub decode: fn(&[u8]) -> Option<UntypedData>
+}
+
+impl Codec {
+	pub fn new<T: Serialize + DeserializeOwned + Send + Sync + 'static>(id: &'static str) -> Self {
+		Self {
+			id,
+			#[cfg(debug_assertions)]
+			type_name: std::any::type_name::<T>(),
+			encode: |value| value.downcast_ref::<T>().and_then(|value| rmp_serde::to_vec(value).ok()),
+			decode: |bytes| rmp_serde::from_slice::<T>(bytes).ok().map(|value| Arc::new(value) as UntypedData)
+		}
+	}
+}
@@ -0,0 +1,79 @@
+use std::{
+	fs, io,
+	path::{Path, PathBuf}
+};
+
+use super::{Storage, StoredCommand, StoredEntry};
+
+/// A [`Storage`] which keeps the cache (or a [`MutationQueue`][crate::MutationQueue]'s commands) in a single file.
+///
+/// Writes are atomic: entries are first written to a temporary file next to `path`, which then replaces the original.
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub struct FileStorage {
+	path: PathBuf
+}
+
+impl FileStorage {
+	/// Creates a new file storage which reads from & writes to `path`.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+
+	/// Returns the path of the backing file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	fn read<T: serde::de::DeserializeOwned>(&self) -> io::Result<Vec<T>> {
+		let bytes = match fs::read(&self.path) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(e)
+		};
+		rmp_serde::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	fn write<T: serde::Serialize>(&self, records: &[T]) -> io::Result<()> {
+		let bytes = rmp_serde::to_vec(records).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+		let mut tmp_path = self.path.clone().into_os_string();
+		tmp_path.push(".tmp");
+		fs::write(&tmp_path, bytes)?;
+		fs::rename(&tmp_path, &self.path)
+	}
+}
+
+impl Storage for FileStorage {
+	type Error = io::Error;
+
+	fn load(&self) -> io::Result<Vec<StoredEntry>> {
+		let entries: Vec<(Vec<u8>, String, Vec<u8>)> = self.read()?;
+		Ok(entries
+			.into_iter()
+			.map(|(key, type_id, data)| StoredEntry { key, type_id, data })
+			.collect())
+	}
+
+	fn store(&self, entries: &[StoredEntry]) -> io::Result<()> {
+		let entries: Vec<(&[u8], &str, &[u8])> = entries
+			.iter()
+			.map(|e| (e.key.as_slice(), e.type_id.as_str(), e.data.as_slice()))
+			.collect();
+		self.write(&entries)
+	}
+}
+
+impl Storage<StoredCommand> for FileStorage {
+	type Error = io::Error;
+
+	fn load(&self) -> io::Result<Vec<StoredCommand>> {
+		let commands: Vec<Vec<u8>> = self.read()?;
+		Ok(commands.into_iter().map(|data| StoredCommand { data }).collect())
+	}
+
+	fn store(&self, commands: &[StoredCommand]) -> io::Result<()> {
+		let commands: Vec<&[u8]> = commands.iter().map(|c| c.data.as_slice()).collect();
+		self.write(&commands)
+	}
+}
@@ -0,0 +1,365 @@
+use std::{
+	borrow::Borrow,
+	collections::VecDeque,
+	future::Future,
+	sync::{Arc, Weak, atomic::Ordering},
+	time::Duration
+};
+
+use parking_lot::Mutex;
+
+#[cfg(feature = "persist")]
+use crate::persist::{PersistError, Storage, StoredCommand};
+use crate::{
+	SWRInner,
+	cache::RebaseFn,
+	fetcher::Fetcher,
+	runtime::{DefaultRuntime, Runtime},
+	util::{TaskSlot, TaskStartMode}
+};
+
+/// A mutation recorded as a command, which is applied to the cache locally and later sent to the server by a
+/// [`MutationQueue`].
+///
+/// Commands are usually an `enum` of each operation the application supports while offline. To survive application
+/// restarts, commands should also implement [`Serialize`][serde::Serialize] &
+/// [`Deserialize`][serde::Deserialize]; see [`MutationQueue::persist`].
+pub trait Command<F: Fetcher>: Send + Sync + 'static {
+	/// The type of data this command modifies; the key's data is of type `F::Response<Self::Data>`.
+	type Data: Send + Sync + 'static;
+
+	/// Returns the key whose data this command modifies.
+	fn key(&self) -> F::Key;
+
+	/// Applies this command to the key's data locally, returning the expected result, or `None` to leave the data
+	/// unchanged.
+	///
+	/// This is called when the command is [pushed][MutationQueue::push], and again each time fresh data for the key is
+	/// fetched while the command is still pending, so it should not have side effects.
+	fn apply(&self, data: Option<&F::Response<Self::Data>>) -> Option<F::Response<Self::Data>>;
+
+	/// Sends this command to the server.
+	fn send(&self, fetcher: &F) -> impl Future<Output = Result<(), F::Error>> + Send;
+
+	/// Returns whether or not sending this command should be retried after failing with `error`.
+	///
+	/// Commands which aren't retried are discarded, and their key is revalidated to undo the command's local changes.
+	/// By default, all errors are retried.
+	fn is_retryable(&self, error: &F::Error) -> bool {
+		let _ = error;
+		true
+	}
+}
+
+struct QueueState<C> {
+	commands: VecDeque<Arc<C>>,
+	online: bool,
+	// whether the replay task is running; guarded by the same lock as `commands` so a newly pushed command is never
+	// missed by a replay task that's about to finish
+	replaying: bool
+}
+
+struct Shared<C: Command<F>, F: Fetcher, R: Runtime> {
+	state: Arc<Mutex<QueueState<C>>>,
+	retry_interval: Duration,
+	replay_task: Mutex<TaskSlot<R>>,
+	inner: Arc<SWRInner<F, R>>
+}
+
+/// A queue of [`Command`]s which are applied to the cache optimistically, and sent to the server in order while
+/// [online][MutationQueue::set_online], created with [`SWR::mutation_queue`][crate::SWR::mutation_queue].
+///
+/// Unlike [`SWR::mutate_with`][crate::SWR::mutate_with], which runs its mutator immediately and rolls back on error,
+/// commands stay in the queue until they are successfully sent, retrying on failure. While a command is pending:
+/// - Its key's data shows the result of [applying][Command::apply] every pending command for that key.
+/// - Fresh data fetched for its key is *rebased*: pending commands are re-applied on top of it.
+/// - Its key's cache entry will not be garbage collected.
+///
+/// Once a command is sent, its key is revalidated so the server's data takes over.
+///
+/// # Cloning
+/// `MutationQueue` is internally reference counted via [`Arc`], so it can be cheaply cloned.
+pub struct MutationQueue<C: Command<F>, F: Fetcher, R: Runtime = DefaultRuntime> {
+	shared: Arc<Shared<C, F, R>>
+}
+
+impl<C: Command<F>, F: Fetcher, R: Runtime> Clone for MutationQueue<C, F, R> {
+	fn clone(&self) -> Self {
+		Self { shared: Arc::clone(&self.shared) }
+	}
+}
+
+impl<C: Command<F>, F: Fetcher, R: Runtime> MutationQueue<C, F, R> {
+	pub(crate) fn new(inner: &Arc<SWRInner<F, R>>, retry_interval: Duration) -> Self {
+		Self {
+			shared: Arc::new(Shared {
+				state: Arc::new(Mutex::new(QueueState {
+					commands: VecDeque::new(),
+					online: true,
+					replaying: false
+				})),
+				retry_interval,
+				replay_task: Mutex::new(TaskSlot::new(inner.runtime.clone())),
+				inner: Arc::clone(inner)
+			})
+		}
+	}
+
+	/// Adds a command to the end of the queue, immediately applying it to its key's data.
+	///
+	/// If the queue is online, the command will be sent once all commands before it have been sent.
+	///
+	/// This function can be used outside of the GUI.
+	pub fn push(&self, command: C) {
+		let command = Arc::new(command);
+		let key = command.key();
+		let inner = &self.shared.inner;
+		let slot = inner.cache.get_or_create_owned(key.clone());
+
+		let mut states = inner.cache.states();
+		states.mutate(slot, |state| {
+			let mut queue = self.shared.state.lock();
+			if !queue.commands.iter().any(|c| c.key() == key) {
+				// the first pending command for this key; hold the entry until all of its commands are sent
+				state.strong_count.fetch_add(1, Ordering::Relaxed);
+				state.rebase = Some(rebase::<C, F>(&self.shared.state, key.clone()));
+			}
+			queue.commands.push_back(Arc::clone(&command));
+			self.shared.start_replay(&mut queue);
+			drop(queue);
+
+			let data = state.data::<C::Data>().and_then(Result::ok);
+			let applied = command.apply(data.as_deref());
+			if let Some(applied) = applied {
+				let previous_data = state.insert(Arc::new(applied));
+				state.record_history(previous_data);
+			}
+		});
+		drop(states);
+
+		inner.hook.request_redraw();
+	}
+
+	/// Sets whether or not the application is online. Commands are only sent while online; this defaults to `true`.
+	///
+	/// A command that is already being sent when going offline is allowed to finish.
+	pub fn set_online(&self, online: bool) {
+		let mut queue = self.shared.state.lock();
+		queue.online = online;
+		if online {
+			self.shared.start_replay(&mut queue);
+		}
+	}
+
+	/// Returns whether or not the queue is online.
+	pub fn is_online(&self) -> bool {
+		self.shared.state.lock().online
+	}
+
+	/// Returns the number of commands waiting to be sent.
+	pub fn len(&self) -> usize {
+		self.shared.state.lock().commands.len()
+	}
+
+	/// Returns `true` if there are no commands waiting to be sent.
+	pub fn is_empty(&self) -> bool {
+		self.shared.state.lock().commands.is_empty()
+	}
+
+	/// Returns the commands waiting to be sent for the given key, in order.
+	pub fn pending<K>(&self, key: &K) -> Vec<Arc<C>>
+	where
+		K: Eq + ?Sized,
+		F::Key: Borrow<K>
+	{
+		self.shared
+			.state
+			.lock()
+			.commands
+			.iter()
+			.filter(|c| c.key().borrow() == key)
+			.cloned()
+			.collect()
+	}
+}
+
+#[cfg(feature = "persist")]
+#[cfg_attr(docsrs, doc(cfg(feature = "persist")))]
+impl<C: Command<F>, F: Fetcher, R: Runtime> MutationQueue<C, F, R> {
+	/// Writes all pending commands to `storage`, returning the number of commands that were written.
+	///
+	/// This replaces everything in `storage`, so the queue should have its own storage, separate from the one used by
+	/// [`SWR::persist`][crate::SWR::persist] and by other queues. Call this after pushing commands so they survive the
+	/// application exiting before they are sent.
+	///
+	/// If any command fails to serialize, nothing is written.
+	pub fn persist<S>(&self, storage: &S) -> Result<usize, PersistError<S::Error>>
+	where
+		C: serde::Serialize,
+		S: Storage<StoredCommand>
+	{
+		let commands = self
+			.shared
+			.state
+			.lock()
+			.commands
+			.iter()
+			.map(|command| rmp_serde::to_vec(&**command).map(|data| StoredCommand { data }))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(PersistError::Encode)?;
+
+		storage.store(&commands).map_err(PersistError::Storage)?;
+		Ok(commands.len())
+	}
+
+	/// [Pushes][MutationQueue::push] the commands previously saved to `storage` by [`MutationQueue::persist`],
+	/// returning the number of commands that were restored.
+	///
+	/// This should be called on startup, *after* [`SWR::hydrate`][crate::SWR::hydrate], so that the commands are
+	/// applied on top of the hydrated data.
+	pub fn restore<S>(&self, storage: &S) -> Result<usize, S::Error>
+	where
+		C: serde::de::DeserializeOwned,
+		S: Storage<StoredCommand>
+	{
+		let mut restored = 0;
+		for stored in storage.load()? {
+			let Ok(command) = rmp_serde::from_slice::<C>(&stored.data) else {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::warn!(command = std::any::type_name::<C>(), "failed to deserialize persisted command");
+				}
+				continue;
+			};
+			self.push(command);
+			restored += 1;
+		}
+		Ok(restored)
+	}
+}
+
+impl<C: Command<F>, F: Fetcher, R: Runtime> Shared<C, F, R> {
+	fn start_replay(self: &Arc<Self>, queue: &mut QueueState<C>) {
+		if !queue.online || queue.replaying || queue.commands.is_empty() {
+			return;
+		}
+
+		queue.replaying = true;
+		// any previous replay task has already given up, even if it hasn't quite finished yet
+		self.replay_task
+			.lock()
+			.insert(TaskStartMode::Override, Self::replay(Arc::downgrade(self)));
+	}
+
+	// The replay task only holds a weak reference to the queue, since the queue owns the task; it stops once every
+	// handle to the queue has been dropped.
+	async fn replay(shared: Weak<Self>) {
+		loop {
+			let (command, inner, retry_interval) = {
+				let Some(shared) = shared.upgrade() else {
+					return;
+				};
+				let mut queue = shared.state.lock();
+				match queue.commands.front().filter(|_| queue.online) {
+					Some(command) => (Arc::clone(command), Arc::clone(&shared.inner), shared.retry_interval),
+					None => {
+						queue.replaying = false;
+						return;
+					}
+				}
+			};
+
+			let res = command.send(&inner.fetcher).await;
+			let complete = match res {
+				Ok(()) => true,
+				Err(err) if command.is_retryable(&err) => {
+					#[cfg(feature = "tracing")]
+					{
+						tracing::info!(key = ?command.key(), "failed to send command, retrying: {err}");
+					}
+
+					inner.runtime.wait(retry_interval).await;
+					false
+				}
+				Err(err) => {
+					#[cfg(feature = "tracing")]
+					{
+						tracing::warn!(key = ?command.key(), "discarding command which failed to send: {err}");
+					}
+					#[cfg(not(feature = "tracing"))]
+					let _ = err;
+
+					true
+				}
+			};
+			if complete {
+				let Some(shared) = shared.upgrade() else {
+					return;
+				};
+				shared.complete(&command);
+			}
+		}
+	}
+
+	/// Removes a command which has been sent (or discarded) from the queue.
+	fn complete(&self, command: &Arc<C>) {
+		let key = command.key();
+		let Some(slot) = self.inner.cache.get(&key) else {
+			return;
+		};
+
+		let mut states = self.inner.cache.states();
+		states.mutate(slot, |state| {
+			let mut queue = self.state.lock();
+			let Some(index) = queue.commands.iter().position(|c| Arc::ptr_eq(c, command)) else {
+				return;
+			};
+			queue.commands.remove(index);
+			if !queue.commands.iter().any(|c| c.key() == key) {
+				state.rebase = None;
+				state.strong_count.fetch_sub(1, Ordering::Release);
+			}
+		});
+		drop(states);
+
+		// the server's data now reflects the command
+		self.inner.revalidate(slot);
+	}
+}
+
+impl<C: Command<F>, F: Fetcher, R: Runtime> Drop for Shared<C, F, R> {
+	fn drop(&mut self) {
+		self.replay_task.get_mut().abort();
+
+		// release the entries of keys with pending commands, since they will never be sent
+		let commands = std::mem::take(&mut self.state.lock().commands);
+		let mut slots = Vec::new();
+		for command in commands {
+			let key = command.key();
+			if let Some(slot) = self.inner.cache.get(&key) {
+				if !slots.contains(&slot) {
+					slots.push(slot);
+				}
+			}
+		}
+
+		let mut states = self.inner.cache.states();
+		for slot in slots {
+			states.mutate(slot, |state| {
+				state.rebase = None;
+				state.strong_count.fetch_sub(1, Ordering::Release);
+			});
+		}
+	}
+}
+
+fn rebase<C: Command<F>, F: Fetcher>(state: &Arc<Mutex<QueueState<C>>>, key: F::Key) -> RebaseFn {
+	let state = Arc::clone(state);
+	Arc::new(move |data| {
+		let mut data = Arc::clone(data).downcast::<F::Response<C::Data>>().ok()?;
+		for command in state.lock().commands.iter().filter(|c| c.key() == key) {
+			data = command.apply(Some(&data)).map(Arc::new).unwrap_or(data);
+		}
+		Some(data as _)
+	})
+}
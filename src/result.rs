@@ -1,6 +1,9 @@
 use std::{
+	borrow::Borrow,
 	future::Future,
-	sync::{Arc, Weak, atomic::Ordering}
+	hash::Hash,
+	sync::{Arc, Weak, atomic::Ordering},
+	time::Duration
 };
 
 use serde::de::DeserializeOwned;
@@ -9,11 +12,11 @@ use crate::{
 	CacheEntryStatus, SWRInner,
 	cache::{CacheSlot, StateAccessor},
 	error::Error,
-	fetcher::Fetcher,
-	options::{MutateOptions, Options, RevalidateFlags},
+	fetcher::{FetchIntent, Fetcher},
+	options::{MutateOptions, Options, RevalidateFlags, Usage},
 	revalidate::{RevalidateIntent, launch_fetch},
 	runtime::{DefaultRuntime, Runtime},
-	util::TaskStartMode
+	util::{Instant, TaskStartMode, random_u64, throttle}
 };
 
 /// A persisted slot in the [cache][crate::SWR].
@@ -22,6 +25,11 @@ use crate::{
 pub struct Persisted<T: Send + Sync + 'static, F: Fetcher, R: Runtime = DefaultRuntime> {
 	slot: CacheSlot,
 	options: Option<Options<F::Response<T>>>,
+	// data from a key this slot previously pointed to, along with the current key's data version at the time; the data
+	// is shown until the current key's data changes. see `Persisted::set_key`
+	previous: Option<(Weak<F::Response<T>>, u64)>,
+	// identifies the callbacks registered by this slot
+	usage: Usage,
 	inner: Arc<SWRInner<F, R>>
 }
 
@@ -32,21 +40,109 @@ where
 	R: Runtime
 {
 	pub(crate) fn new(swr: &Arc<SWRInner<F, R>>, slot: CacheSlot, options: Option<Options<F::Response<T>>>) -> Self {
-		{
-			let states = swr.cache.states();
-			if let Some(state) = states.get(slot) {
-				state.strong_count.fetch_add(1, Ordering::Relaxed);
-				if let Some(options) = options.as_ref() {
-					state.options.write().update_from(options);
-				}
-			}
-		}
+		Self::new_with_usage(swr, slot, options, Usage::Persisted(random_u64()))
+	}
 
-		Self {
+	/// Creates a slot which is only used for the current frame, i.e. whose callbacks outlive it until the next frame.
+	pub(crate) fn new_for_frame(swr: &Arc<SWRInner<F, R>>, slot: CacheSlot, options: Option<Options<F::Response<T>>>) -> Self {
+		Self::new_with_usage(swr, slot, options, Usage::Frame)
+	}
+
+	fn new_with_usage(swr: &Arc<SWRInner<F, R>>, slot: CacheSlot, options: Option<Options<F::Response<T>>>, usage: Usage) -> Self {
+		let persisted = Self {
 			slot,
 			options,
+			previous: None,
+			usage,
 			inner: Arc::clone(swr)
+		};
+		persisted.acquire();
+		persisted
+	}
+
+	fn acquire(&self) {
+		if let Some(state) = self.inner.cache.states().get(self.slot) {
+			state.strong_count.fetch_add(1, Ordering::Relaxed);
+		}
+		if let Some(options) = &self.options {
+			self.register(options);
+		}
+	}
+
+	/// Merges `options` into the slot's cache entry, registering its callbacks on behalf of this slot.
+	pub(crate) fn register(&self, options: &Options<F::Response<T>>) {
+		options.callbacks.check_key_type::<F::Key>();
+		let states = self.inner.cache.states();
+		if let Some(state) = states.get(self.slot) {
+			let added_tags = {
+				let mut stored_options = state.options.write();
+				stored_options.update_from(options);
+				stored_options.register_callbacks(self.usage, &options.callbacks);
+				stored_options.add_tags(&options.tags)
+			};
+			self.inner.cache.tag(self.slot, added_tags);
+		}
+	}
+
+	/// Re-points this slot at a different key.
+	///
+	/// Until the new key has data of its own, [`get`][Persisted::get] returns the previous key's data instead, marked
+	/// by [`FetchResult::is_previous_data`]. This is useful for e.g. search fields, where the results for the previous
+	/// query should stay on screen while the new query loads:
+	/// ```
+	/// # struct Ui;
+	/// # struct Response;
+	/// # impl Ui {
+	/// # 	fn text_edit_singleline(&mut self, _: &mut String) -> Response { Response }
+	/// # }
+	/// # impl Response {
+	/// # 	fn changed(&self) -> bool { true }
+	/// # }
+	/// # #[derive(serde::Deserialize)]
+	/// # struct SearchResults;
+	/// # struct Search<F: swr::Fetcher> {
+	/// # 	query: String,
+	/// # 	results: swr::Persisted<SearchResults, F>
+	/// # }
+	/// # impl<F: swr::Fetcher<Key = String>> Search<F> {
+	/// # fn render(&mut self, ui: &mut Ui) {
+	/// if ui.text_edit_singleline(&mut self.query).changed() {
+	/// 	self.results.set_key(&self.query);
+	/// }
+	/// # }
+	/// # }
+	/// ```
+	///
+	/// The previous key's cache entry is released, allowing it to be garbage collected; its data is no longer shown
+	/// once that happens.
+	pub fn set_key<K>(&mut self, key: &K)
+	where
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		let slot = self.inner.cache.get_or_create(key);
+		self.set_slot(slot);
+	}
+
+	pub(crate) fn set_slot(&mut self, slot: CacheSlot) {
+		if slot == self.slot {
+			return;
 		}
+
+		let states = self.inner.cache.states();
+		// if the old key never loaded, keep showing whatever was shown before it
+		let previous = match states.get(self.slot).and_then(|state| state.data::<T>()) {
+			Some(Ok(data)) => Some(Arc::downgrade(&data)),
+			_ => self.previous.take().map(|(data, _)| data)
+		};
+		let version = states.get(slot).map_or(0, |state| state.data_version());
+		self.previous = previous.map(|data| (data, version));
+		drop(states);
+
+		self.release();
+		self.slot = slot;
+		self.acquire();
+		self.inner.hook.request_redraw();
 	}
 
 	/// Triggers the cache entry to revalidate.
@@ -62,7 +158,7 @@ where
 	/// [`Persisted::get_shallow`].
 	pub fn get(&self) -> FetchResult<T, F, R> {
 		let states = self.inner.cache.states();
-		self.get_inner(states, true)
+		self.get_inner(states, true, true)
 	}
 
 	/// Returns this slot's entry in the cache.
@@ -71,32 +167,51 @@ where
 	/// use outside of the GUI.
 	pub fn get_shallow(&self) -> FetchResult<T, F, R> {
 		let states = self.inner.cache.states();
-		self.get_inner(states, false)
+		self.get_inner(states, false, false)
 	}
 
-	fn get_inner(&self, mut states: StateAccessor<'_, F, R>, update: bool) -> FetchResult<T, F, R> {
+	pub(crate) fn get_inner(&self, mut states: StateAccessor<'_, F, R>, update: bool, allow_focus_revalidation: bool) -> FetchResult<T, F, R> {
 		let Some(state) = states.get(self.slot) else {
 			return FetchResult::new_empty(self.slot, Arc::downgrade(&self.inner));
 		};
-		let status = state.status().load(Ordering::Acquire);
+		let mut status = state.status().load(Ordering::Acquire);
 		let was_alive = status & CacheEntryStatus::ALIVE != 0;
 
 		let mut error = state.error().map(|e| Error::Fetcher(Arc::clone(e)));
+		let mut previous = false;
 		let data = match state.data::<T>() {
 			Some(Ok(data)) => Some(data),
-			Some(Err(e)) => {
-				error = error.or(Some(Error::MismatchedType(e)));
-				self.options.as_ref().and_then(|o| o.fallback.clone())
+			other => {
+				if let Some(Err(e)) = other {
+					error = error.or(Some(Error::MismatchedType(e)));
+				}
+				// the previous key's data is only shown until the current key is populated; once the current key has been
+				// mutated, fetched or invalidated, showing it again would be misleading
+				let previous_data = self
+					.previous
+					.as_ref()
+					.filter(|(_, version)| *version == state.data_version())
+					.and_then(|(data, _)| data.upgrade());
+				previous = previous_data.is_some();
+				previous_data.or_else(|| self.options.as_ref().and_then(|o| o.fallback.clone()))
 			}
-			None => self.options.as_ref().and_then(|o| o.fallback.clone())
 		};
 		let (mut loading, mut validating) = (status & CacheEntryStatus::LOADING != 0, status & CacheEntryStatus::VALIDATING != 0);
+		let (data_updated_at, error_updated_at) = (state.data_updated_time(Ordering::Acquire), state.error_updated_time(Ordering::Acquire));
+		let (fetch_count, failure_count, last_fetch_duration) = (state.fetch_count, state.retry_count.load(Ordering::Acquire), state.last_fetch_duration);
+		let mut fetch_intent = state.fetch_intent;
+		let retry_at = state.retry_at.filter(|_| !state.retry_task.is_finished());
+		let enabled = self.options.as_ref().is_none_or(|o| o.enabled);
+
+		let options = state.options.read();
+		// data is fresh for `stale_time` after the last successful fetch
+		let fresh = !throttle(state.last_fetch_time(Ordering::Acquire), options.stale_time());
+		let stale = status & CacheEntryStatus::HAS_DATA != 0 && !fresh;
 
 		if update {
 			let intent = state.revalidate_intent();
-			let options = state.options.read();
 
-			if self.inner.hook.was_focus_triggered() && options.revalidate_flags.get(RevalidateFlags::ON_FOCUS) {
+			if enabled && allow_focus_revalidation && self.inner.hook.was_focus_triggered() && options.revalidate_flags.get(RevalidateFlags::ON_FOCUS) {
 				let throttled = match options.focus_throttle_interval() {
 					Some(throttle) => state.last_draw_time(Ordering::Acquire).elapsed() < throttle,
 					None => false
@@ -106,48 +221,115 @@ where
 				}
 			}
 
-			if !was_alive {
+			// Subscribed keys are kept up to date by their subscription, which is (re)started when the key comes into use.
+			let subscriber = state.subscriber.filter(|_| !was_alive && state.subscription_task.is_finished());
+			if enabled && !was_alive && state.subscriber.is_none() {
 				if (options.revalidate_flags.get(RevalidateFlags::ON_FIRST_USE) && data.is_none())
 					// fetch task aborted before it could finish. instead of having the key be forever stuck in the
 					// loading state, restart the initial fetch
 					|| (loading && state.fetch_task.is_finished())
 				{
 					intent.add(RevalidateIntent::FIRST_USAGE);
-				} else {
-					// TODO: configurable
+				} else if !fresh {
 					intent.add(RevalidateIntent::STALE);
 				}
+			} else if enabled
+				&& state.subscriber.is_none()
+				&& options.revalidate_flags.get(RevalidateFlags::ON_FIRST_USE)
+				&& data.is_none()
+				&& !loading && state.last_request_time(Ordering::Acquire).is_none()
+			{
+				// key was previously disabled and has never been fetched
+				intent.add(RevalidateIntent::FIRST_USAGE);
 			}
 
 			state.mark_used();
 
-			let intent = intent.take();
-			if intent != 0 {
+			let mut intent = intent.take();
+			if intent & RevalidateIntent::DEDUPABLE != 0 && fresh {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::debug!(
+						key = ?state.key(),
+						"skipped revalidation of fresh data due to: {}",
+						RevalidateIntent::describe(intent & RevalidateIntent::DEDUPABLE)
+					);
+				}
+
+				intent &= !RevalidateIntent::DEDUPABLE;
+			}
+			if intent & RevalidateIntent::DEDUPABLE != 0 && !throttle(state.last_fetch_time(Ordering::Acquire), options.dedupe_interval()) {
+				#[cfg(feature = "tracing")]
+				{
+					tracing::debug!(
+						key = ?state.key(),
+						"deduplicated revalidation due to: {}",
+						RevalidateIntent::describe(intent & RevalidateIntent::DEDUPABLE)
+					);
+				}
+
+				intent &= !RevalidateIntent::DEDUPABLE;
+			}
+			if intent != 0 || subscriber.is_some() {
 				drop((state, options));
 				states.mutate(self.slot, |state| {
-					launch_fetch::<T, F, R>(
-						state,
-						&self.inner,
-						self.slot,
-						if intent & RevalidateIntent::MANUALLY_TRIGGERED != 0 {
-							TaskStartMode::Abort
-						} else {
-							TaskStartMode::Soft
-						},
-						intent
-					);
+					if let Some(subscribe) = subscriber {
+						subscribe(state, &self.inner, self.slot);
+					}
+					if intent != 0 {
+						launch_fetch::<T, F, R>(
+							state,
+							&self.inner,
+							self.slot,
+							if intent & RevalidateIntent::MANUALLY_TRIGGERED != 0 {
+								TaskStartMode::Abort
+							} else {
+								TaskStartMode::Soft
+							},
+							intent
+						);
+					}
 
-					let status = state.status().load(Ordering::Relaxed);
+					status = state.status().load(Ordering::Relaxed);
 					(loading, validating) = (status & CacheEntryStatus::LOADING != 0, status & CacheEntryStatus::VALIDATING != 0);
+					fetch_intent = state.fetch_intent;
 				});
 			}
 		}
 
+		let fetch_status = if loading {
+			FetchStatus::Loading
+		} else if validating {
+			FetchStatus::Refreshing {
+				previous: if error.is_some() { Settled::Error } else { Settled::Success }
+			}
+		} else if error.is_some() {
+			match retry_at {
+				Some(at) => FetchStatus::RetryScheduled { at },
+				None => FetchStatus::Error
+			}
+		} else if status & CacheEntryStatus::HAS_DATA != 0 {
+			FetchStatus::Success
+		} else if !enabled {
+			FetchStatus::Paused
+		} else {
+			FetchStatus::Idle
+		};
+
 		FetchResult {
 			data,
 			error,
+			status: fetch_status,
 			loading,
 			validating,
+			data_updated_at,
+			error_updated_at,
+			fetch_count,
+			failure_count,
+			last_fetch_duration,
+			fetch_intent: (fetch_intent != 0).then_some(FetchIntent(fetch_intent)),
+			stale,
+			previous,
 			slot: self.slot,
 			inner: Arc::downgrade(&self.inner)
 		}
@@ -179,24 +361,254 @@ where
 	{
 		self.inner.mutate_with(self.slot, options, mutator)
 	}
+
+	/// Creates a [`Selector`] which projects a value out of this slot's data with `select`.
+	///
+	/// The projection is memoized, so `select` only runs when new data is inserted into the cache, and
+	/// [`Selector::changed`] reports whether the selected value actually differs.
+	pub fn selector<U, S>(self, select: S) -> Selector<T, U, F, R>
+	where
+		U: PartialEq,
+		S: Fn(&F::Response<T>) -> U + Send + Sync + 'static
+	{
+		Selector {
+			persisted: self,
+			select: Box::new(select),
+			source: Weak::new(),
+			value: None,
+			changed: false
+		}
+	}
+}
+
+impl<T: Send + Sync + 'static, F: Fetcher, R: Runtime> Persisted<T, F, R> {
+	fn release(&self) {
+		let states = self.inner.cache.states();
+		if let Some(state) = states.get(self.slot) {
+			// frame usages' callbacks are kept until the end of the frame
+			if let Usage::Persisted(_) = self.usage {
+				state.options.write().unregister_callbacks(self.usage);
+			}
+			state.strong_count.fetch_sub(1, Ordering::Release);
+		}
+	}
 }
 
 impl<T: Send + Sync + 'static, F: Fetcher, R: Runtime> Drop for Persisted<T, F, R> {
 	fn drop(&mut self) {
-		let states = self.inner.cache.states();
-		let Some(state) = states.get(self.slot) else {
-			return;
-		};
-		state.strong_count.fetch_sub(1, Ordering::Release);
+		self.release();
 	}
 }
 
+type SelectFn<D, U> = Box<dyn Fn(&D) -> U + Send + Sync>;
+
+/// A memoized projection of a [`Persisted`] slot's data, created by [`Persisted::selector`].
+///
+/// The selected value is cached alongside the identity of the data it was selected from, so the selector function
+/// only runs when the slot's data is replaced - not every frame.
+/// ```
+/// # #[derive(serde::Deserialize)]
+/// # struct Profile;
+/// // e.g. `let name = swr.persisted::<Profile, _>(&key, None).selector(|profile| profile.name.clone());`
+/// fn render<F: swr::Fetcher>(name: &mut swr::Selector<Profile, String, F>) {
+/// 	if let Some(name) = name.get() {
+/// 		// draw `name`...
+/// 	}
+/// 	if name.changed() {
+/// 		// the name is different from the last frame
+/// 	}
+/// }
+/// ```
+pub struct Selector<T: Send + Sync + 'static, U, F: Fetcher, R: Runtime = DefaultRuntime> {
+	persisted: Persisted<T, F, R>,
+	select: SelectFn<F::Response<T>, U>,
+	// the data `value` was selected from; a `Weak` keeps the allocation (and thus its address) from being reused
+	source: Weak<F::Response<T>>,
+	value: Option<U>,
+	changed: bool
+}
+
+impl<T, U, F, R> Selector<T, U, F, R>
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	U: PartialEq,
+	F: Fetcher,
+	R: Runtime
+{
+	/// Returns the value selected from the slot's data, or `None` if the slot has no data.
+	///
+	/// This should only be used during the GUI's rendering process. For use outside of the GUI, see
+	/// [`Selector::get_shallow`].
+	pub fn get(&mut self) -> Option<&U> {
+		let result = self.persisted.get();
+		self.update(result.data);
+		self.value.as_ref()
+	}
+
+	/// Returns the value selected from the slot's data, or `None` if the slot has no data.
+	///
+	/// Like [`Persisted::get_shallow`], this does not contribute to the lifecycle of the cache entry, thus it is
+	/// suitable for use outside of the GUI.
+	pub fn get_shallow(&mut self) -> Option<&U> {
+		let result = self.persisted.get_shallow();
+		self.update(result.data);
+		self.value.as_ref()
+	}
+
+	fn update(&mut self, data: Option<Arc<F::Response<T>>>) {
+		match data {
+			Some(data) => {
+				if self.source.upgrade().is_some_and(|source| Arc::ptr_eq(&source, &data)) {
+					self.changed = false;
+					return;
+				}
+
+				let value = (self.select)(&data);
+				self.changed = self.value.as_ref() != Some(&value);
+				self.value = Some(value);
+				self.source = Arc::downgrade(&data);
+			}
+			None => {
+				self.changed = self.value.take().is_some();
+				self.source = Weak::new();
+			}
+		}
+	}
+
+	/// Returns `true` if the value returned by the last call to [`Selector::get`] differs from the value returned by
+	/// the call before it.
+	#[inline]
+	pub fn changed(&self) -> bool {
+		self.changed
+	}
+
+	/// Returns the underlying [`Persisted`] slot.
+	#[inline]
+	pub fn persisted(&self) -> &Persisted<T, F, R> {
+		&self.persisted
+	}
+
+	/// Consumes the selector, returning the underlying [`Persisted`] slot.
+	pub fn into_persisted(self) -> Persisted<T, F, R> {
+		self.persisted
+	}
+}
+
+/// The status of a key's entry in the cache, as returned in [`FetchResult::status`].
+///
+/// Unlike the [`loading`][FetchResult::loading] & [`validating`][FetchResult::validating] flags, exactly one status
+/// applies at a time:
+/// ```
+/// fn render<F: swr::Fetcher>(result: swr::Result<u32, F>) {
+/// 	match result.status {
+/// 		swr::FetchStatus::Loading => { /* show a spinner */ }
+/// 		swr::FetchStatus::RetryScheduled { .. } | swr::FetchStatus::Error => { /* show the error */ }
+/// 		_ => { /* show the data */ }
+/// 	}
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchStatus {
+	/// The key has no data or error, and no fetch is in progress, e.g. because
+	/// [`Options::fetch_on_first_use`] is `false`.
+	Idle,
+	/// The key has no data, and its initial fetch is in progress.
+	Loading,
+	/// The key has data, and no fetch is in progress.
+	Success,
+	/// The most recent fetch failed, and no retry is scheduled.
+	Error,
+	/// The key is being revalidated, while its previous data (and possibly error) is still available.
+	Refreshing {
+		/// The outcome of the previous fetch.
+		previous: Settled
+	},
+	/// The most recent fetch failed, and will be [retried][Options::error_retry_interval] at `at`.
+	RetryScheduled {
+		/// When the retry will occur.
+		at: Instant
+	},
+	/// The key has no data, and won't be fetched because this usage is [disabled][Options::enabled].
+	Paused
+}
+
+/// The outcome of a key's previous fetch, as stored in [`FetchStatus::Refreshing`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Settled {
+	/// The previous fetch succeeded (or the key's data was mutated).
+	Success,
+	/// The previous fetch failed.
+	Error
+}
+
+impl FetchStatus {
+	/// Returns `true` if the key's initial fetch is in progress.
+	#[inline]
+	pub fn is_loading(&self) -> bool {
+		matches!(self, Self::Loading)
+	}
+
+	/// Returns `true` if the key is being revalidated while its previous data is available.
+	#[inline]
+	pub fn is_refreshing(&self) -> bool {
+		matches!(self, Self::Refreshing { .. })
+	}
+
+	/// Returns `true` if any fetch is in progress, i.e. the status is [`Loading`][Self::Loading] or
+	/// [`Refreshing`][Self::Refreshing].
+	#[inline]
+	pub fn is_fetching(&self) -> bool {
+		matches!(self, Self::Loading | Self::Refreshing { .. })
+	}
+
+	/// Returns `true` if the most recent fetch failed, regardless of whether it will be retried.
+	#[inline]
+	pub fn is_error(&self) -> bool {
+		matches!(self, Self::Error | Self::RetryScheduled { .. })
+	}
+
+	/// Returns `true` if the key has data and no fetch is in progress.
+	#[inline]
+	pub fn is_success(&self) -> bool {
+		matches!(self, Self::Success)
+	}
+}
+
+/// The state of a key's entry in the cache, as returned by [`SWR::get`][crate::SWR::get] or [`Persisted::get`].
 #[derive(Clone)]
 pub struct FetchResult<T: Send + Sync + 'static, F: Fetcher, R: Runtime = DefaultRuntime> {
+	/// The key's data, or the [fallback][Options::fallback] if the cache has not yet been populated.
 	pub data: Option<Arc<F::Response<T>>>,
+	/// The error from the most recent fetch, if it failed.
 	pub error: Option<Error<F>>,
+	/// The status of the key's entry; see [`FetchStatus`].
+	pub status: FetchStatus,
+	/// Whether or not the initial fetch is in progress (i.e. the key has no data yet).
+	///
+	/// Equivalent to [`FetchStatus::is_loading`].
 	pub loading: bool,
+	/// Whether or not a revalidation is in progress while stale data is being shown.
+	///
+	/// Equivalent to [`FetchStatus::is_refreshing`].
 	pub validating: bool,
+	/// When the key's data was last updated, either by a fetch or a mutation.
+	///
+	/// `None` if the key has no data, or if its data was [restored from storage][crate::SWR::hydrate].
+	pub data_updated_at: Option<Instant>,
+	/// When the key's most recent error occurred, or `None` if a fetch has never failed.
+	///
+	/// Unlike [`FetchResult::error`], this is not cleared when a subsequent fetch succeeds.
+	pub error_updated_at: Option<Instant>,
+	/// The number of fetches of this key which have completed, successfully or not.
+	pub fetch_count: u32,
+	/// The number of consecutive failed fetches; reset once a fetch succeeds.
+	pub failure_count: u8,
+	/// How long the most recently completed fetch took.
+	pub last_fetch_duration: Option<Duration>,
+	/// The reason(s) the fetch currently in progress was launched, or `None` if no fetch is in progress.
+	pub fetch_intent: Option<FetchIntent>,
+	stale: bool,
+	previous: bool,
 	slot: CacheSlot,
 	inner: Weak<SWRInner<F, R>>
 }
@@ -206,13 +618,46 @@ impl<T: Send + Sync + 'static, F: Fetcher, R: Runtime> FetchResult<T, F, R> {
 		FetchResult {
 			data: None,
 			error: None,
+			status: FetchStatus::Idle,
 			loading: false,
 			validating: false,
+			data_updated_at: None,
+			error_updated_at: None,
+			fetch_count: 0,
+			failure_count: 0,
+			last_fetch_duration: None,
+			fetch_intent: None,
+			stale: false,
+			previous: false,
 			slot,
 			inner
 		}
 	}
 
+	/// Returns `true` if the key has data in the cache which is older than its [stale time][Options::stale_time], i.e.
+	/// the data would be revalidated the next time the key comes into use.
+	///
+	/// This is always `false` if the key has no data (in which case [`FetchResult::data`] may be the fallback).
+	#[inline]
+	pub fn is_stale(&self) -> bool {
+		self.stale
+	}
+
+	/// Projects a value out of [`FetchResult::data`], returning `None` if there is no data.
+	///
+	/// To avoid re-running the projection every frame, see [`Persisted::selector`].
+	#[inline]
+	pub fn select<U>(&self, select: impl FnOnce(&F::Response<T>) -> U) -> Option<U> {
+		self.data.as_deref().map(select)
+	}
+
+	/// Returns `true` if [`FetchResult::data`] is the data of the key a [`Persisted`] slot pointed to before
+	/// [`Persisted::set_key`] was called, because the current key has no data yet.
+	#[inline]
+	pub fn is_previous_data(&self) -> bool {
+		self.previous
+	}
+
 	/// Triggers the cache entry to revalidate.
 	///
 	/// This function can be used outside of the GUI.
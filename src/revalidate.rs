@@ -1,23 +1,25 @@
 use std::{
-	num::NonZeroU8,
+	future::{Future, poll_fn},
+	pin::pin,
 	sync::{
 		Arc,
 		atomic::{AtomicU8, Ordering}
 	},
+	task::Poll,
 	time::Duration
 };
 
+use futures_core::Stream;
 use serde::de::DeserializeOwned;
 
-#[cfg(feature = "tracing")]
-use crate::util::Instant;
 use crate::{
 	CacheEntryStatus, SWRInner,
+	batch::PendingGuard,
 	cache::{CacheEntry, CacheSlot},
-	fetcher::Fetcher,
-	options::RevalidateFlags,
+	fetcher::{CancellationToken, FetchContext, FetchIntent, Fetched, Fetcher, Subscriber},
+	options::{ErrorCallback, RevalidateFlags, SuccessCallback},
 	runtime::Runtime,
-	util::{AtomicBitwise, TaskStartMode, throttle}
+	util::{AtomicBitwise, Instant, Oneshot, TaskStartMode, throttle}
 };
 
 #[derive(Default)]
@@ -33,6 +35,9 @@ impl RevalidateIntent {
 	pub const STALE: u8 = 1 << 5;
 	pub const MUTATE: u8 = 1 << 6;
 
+	/// Intents which are dropped if they occur within the [dedupe interval][crate::Options::dedupe_interval].
+	pub const DEDUPABLE: u8 = Self::APPLICATION_FOCUSED | Self::FIRST_USAGE | Self::STALE;
+
 	pub fn add(&self, flag: u8) -> bool {
 		self.0.bits_set(flag, Ordering::AcqRel)
 	}
@@ -69,63 +74,232 @@ impl RevalidateIntent {
 	}
 }
 
+/// The result of a fetch, along with the callbacks to notify of it.
+enum FetchOutcome<D, E> {
+	Success(Vec<SuccessCallback<D>>, Arc<D>),
+	Error(Vec<ErrorCallback>, Arc<E>),
+	NotModified,
+	/// The entry was mutated, or a newer fetch completed, while the fetch was in progress.
+	Superseded
+}
+
 pub fn launch_fetch<T, F, R>(entry: &mut CacheEntry<F, R>, inner: &Arc<SWRInner<F, R>>, slot: CacheSlot, mode: TaskStartMode, intent: u8)
 where
 	T: DeserializeOwned + Send + Sync + 'static,
 	F: Fetcher,
 	R: Runtime
 {
-	let inner = Arc::clone(inner);
-	let key = entry.key().clone();
-	let did_launch = entry.fetch_task.insert(mode, async move {
-		#[cfg(feature = "tracing")]
-		{
-			tracing::debug!(key = ?key, "fetch triggered due to: {}", RevalidateIntent::describe(intent));
+	let batch = inner.batch::<T>();
+	let batch_result = batch.as_ref().map(|_| Arc::new(Oneshot::default()));
+	// removes the key from the batch if the fetch is aborted before the batch is dispatched
+	let batch_guard = batch
+		.as_ref()
+		.zip(batch_result.as_ref())
+		.map(|(batch, batch_result)| PendingGuard::new(Arc::clone(batch), Arc::clone(batch_result)));
+
+	// Callbacks are only notified of slow loading if the entry has no data to show in the meantime.
+	let loading_slow = {
+		let options = entry.options.read();
+		match options.loading_timeout() {
+			Some(loading_timeout) if !entry.status().get(CacheEntryStatus::HAS_DATA, Ordering::Relaxed) => {
+				let callbacks: Vec<_> = options
+					.callbacks::<F::Response<T>>()
+					.flat_map(|callbacks| callbacks.on_loading_slow.iter().cloned())
+					.collect();
+				(!callbacks.is_empty()).then_some((loading_timeout, callbacks))
+			}
+			_ => None
 		}
+	};
 
-		#[cfg(feature = "tracing")]
-		let before = Instant::now();
+	let has_data = entry.status().get(CacheEntryStatus::HAS_DATA, Ordering::Relaxed);
+	let context = FetchContext {
+		intent: FetchIntent(intent),
+		attempt: entry.retry_count.load(Ordering::Acquire),
+		previous_data_age: has_data
+			.then(|| entry.data_updated_time(Ordering::Acquire).map(|time| time.elapsed()))
+			.flatten(),
+		// a conditional request is pointless if there's no data to keep
+		validators: has_data.then(|| entry.validators.clone()).flatten(),
+		cancellation: CancellationToken::default()
+	};
+	let cancellation = context.cancellation.clone();
 
-		let res = inner.fetcher.fetch::<T>(&key).await;
-		let mut states = inner.cache.states();
-		states.mutate(slot, |state| {
-			match res {
-				Ok(data) => {
+	// results are discarded if the entry is mutated, or a fetch launched after this one completes, in the meantime
+	let version = entry.next_version();
+
+	let key = entry.key().clone();
+	let did_launch = entry.fetch_task.insert_cancellable(mode, Some(cancellation), {
+		let inner = Arc::clone(inner);
+		let key = key.clone();
+		async move {
+			#[cfg(feature = "tracing")]
+			{
+				tracing::debug!(key = ?key, "fetch triggered due to: {}", RevalidateIntent::describe(intent));
+			}
+
+			let before = Instant::now();
+
+			let fetch = async {
+				match &batch_guard {
+					Some(batch_guard) => {
+						let res = batch_guard.recv().await;
+						match res {
+							Some(res) => res.map(Fetched::Data),
+							// the batch fetcher didn't return a result for this key
+							None => inner.fetcher.fetch_with_context::<T>(&key, &context).await
+						}
+					}
+					None => inner.fetcher.fetch_with_context::<T>(&key, &context).await
+				}
+			};
+			let res = match loading_slow {
+				Some((loading_timeout, callbacks)) => {
+					let mut fetch = pin!(fetch);
+					let mut timeout = pin!(inner.runtime.wait(loading_timeout));
+					let mut timed_out = false;
+					poll_fn(|cx| {
+						if let Poll::Ready(res) = fetch.as_mut().poll(cx) {
+							return Poll::Ready(res);
+						}
+						if !timed_out && timeout.as_mut().poll(cx).is_ready() {
+							timed_out = true;
+							for callback in &callbacks {
+								callback(&key);
+							}
+						}
+						Poll::Pending
+					})
+					.await
+				}
+				None => fetch.await
+			};
+
+			let mut states = inner.cache.states();
+			let outcome = states.mutate(slot, |state| {
+				state.fetch_count = state.fetch_count.saturating_add(1);
+				state.last_fetch_duration = Some(before.elapsed());
+				state.finish_fetch(version);
+
+				if state.is_superseded(version) {
 					#[cfg(feature = "tracing")]
 					{
-						tracing::info!(key = ?key, "OK {}ms", before.elapsed().as_millis());
+						tracing::debug!(key = ?key, "discarding result of superseded fetch");
 					}
 
-					state.insert(Arc::new(data));
-
+					// this may have been a refresh, which would otherwise never be rescheduled
 					let refresh_interval = { state.options.read().refresh_interval() };
 					if let Some(refresh_interval) = refresh_interval {
 						launch_refresh::<T, F, R>(state, &inner, slot, refresh_interval);
 					}
+					return FetchOutcome::Superseded;
 				}
-				Err(err) => {
-					#[cfg(feature = "tracing")]
-					{
-						tracing::info!(key = ?key, "ERR {}ms: {err}", before.elapsed().as_millis());
+
+				match res {
+					Ok(Fetched::Data(data)) => {
+						#[cfg(feature = "tracing")]
+						{
+							tracing::info!(key = ?key, "OK {}ms", before.elapsed().as_millis());
+						}
+
+						inner.cache.retag_fetched(slot, state, inner.fetcher.tags::<T>(&key, &data));
+						let validators = inner.fetcher.validators::<T>(&key, &data);
+						let data = state.insert_fetched(Arc::new(data));
+						state.set_data_version(version);
+						state.validators = validators;
+						state.mark_fetched();
+
+						let (refresh_interval, callbacks) = {
+							let options = state.options.read();
+							let callbacks = options
+								.callbacks::<F::Response<T>>()
+								.flat_map(|callbacks| callbacks.on_success.iter().cloned())
+								.collect();
+							(options.refresh_interval(), callbacks)
+						};
+						if let Some(refresh_interval) = refresh_interval {
+							launch_refresh::<T, F, R>(state, &inner, slot, refresh_interval);
+						}
+						inner.hook.request_redraw();
+						FetchOutcome::Success(callbacks, data)
+					}
+					Ok(Fetched::NotModified) => {
+						#[cfg(feature = "tracing")]
+						{
+							tracing::info!(key = ?key, "NOT MODIFIED {}ms", before.elapsed().as_millis());
+						}
+
+						// Keep the existing data, so anything derived from it doesn't need to be recomputed.
+						state.mark_not_modified();
+						state.set_data_version(version);
+						state.mark_fetched();
+						if state.data_untyped().is_none() {
+							// the data was dropped while the fetch was in progress, so there's nothing left to keep
+							state.revalidate_intent().add(RevalidateIntent::STALE);
+						}
+
+						let refresh_interval = { state.options.read().refresh_interval() };
+						if let Some(refresh_interval) = refresh_interval {
+							launch_refresh::<T, F, R>(state, &inner, slot, refresh_interval);
+						}
+						// the loading state & any error were cleared
+						inner.hook.request_redraw();
+						FetchOutcome::NotModified
 					}
+					Err(err) => {
+						#[cfg(feature = "tracing")]
+						{
+							tracing::info!(key = ?key, "ERR {}ms: {err}", before.elapsed().as_millis());
+						}
 
-					state.insert_error(Arc::new(err));
+						let err = Arc::new(err);
+						state.insert_error(Arc::clone(&err));
+						state.set_data_version(version);
 
-					let retry_count = state.retry_count.fetch_add(1, Ordering::AcqRel);
-					let options = state.options.read();
-					if let Some(retry_interval) = options.error_retry_interval() {
-						let max_count = options.error_retry_count.map_or(0, NonZeroU8::get);
-						if max_count == 0 || retry_count < max_count {
-							drop(options);
-							launch_retry::<T, F, R>(state, &inner, slot, retry_interval);
+						let retry_count = state.retry_count.fetch_add(1, Ordering::AcqRel);
+						let previous_delay = (retry_count > 0).then(|| Duration::from_millis(state.last_retry_delay_ms.load(Ordering::Acquire)));
+						let (retry_delay, callbacks) = {
+							let options = state.options.read();
+							let callbacks = options
+								.callbacks::<F::Response<T>>()
+								.flat_map(|callbacks| callbacks.on_error.iter().cloned())
+								.collect();
+							(options.retry_delay(retry_count, &*err, previous_delay), callbacks)
+						};
+						if let Some(retry_delay) = retry_delay {
+							state.last_retry_delay_ms.store(retry_delay.as_millis() as u64, Ordering::Release);
+							launch_retry::<T, F, R>(state, &inner, slot, retry_delay);
 						}
+						inner.hook.request_redraw();
+						FetchOutcome::Error(callbacks, err)
 					}
 				}
+			});
+			drop(states);
+
+			// Callbacks are invoked after releasing the cache lock so that they may freely use the cache themselves.
+			match outcome {
+				Some(FetchOutcome::Success(callbacks, data)) => {
+					for callback in callbacks {
+						callback(&key, &data);
+					}
+				}
+				Some(FetchOutcome::Error(callbacks, err)) => {
+					for callback in callbacks {
+						callback(&key, &*err);
+					}
+				}
+				Some(FetchOutcome::NotModified | FetchOutcome::Superseded) | None => {}
 			}
-			inner.hook.request_redraw();
-		});
+		}
 	});
 	if did_launch {
+		if let (Some(batch), Some(batch_result)) = (batch, batch_result) {
+			batch.enqueue(inner, key, batch_result);
+		}
+
+		entry.start_fetch(version, intent);
+
 		let status = entry.status();
 		if status.get(CacheEntryStatus::HAS_DATA, Ordering::Relaxed) {
 			status.set(CacheEntryStatus::VALIDATING, Ordering::Relaxed);
@@ -177,6 +351,7 @@ where
 	R: Runtime
 {
 	let inner = Arc::clone(inner);
+	entry.retry_at = Some(Instant::now() + retry_interval);
 	entry.retry_task.insert(TaskStartMode::Abort, async move {
 		inner.runtime.wait(retry_interval).await;
 
@@ -197,3 +372,55 @@ where
 		});
 	});
 }
+
+pub fn launch_subscription<T, F, R>(entry: &mut CacheEntry<F, R>, inner: &Arc<SWRInner<F, R>>, slot: CacheSlot)
+where
+	T: DeserializeOwned + Send + Sync + 'static,
+	F: Subscriber,
+	R: Runtime
+{
+	let inner = Arc::clone(inner);
+	let key = entry.key().clone();
+	let did_launch = entry.subscription_task.insert(TaskStartMode::Soft, async move {
+		#[cfg(feature = "tracing")]
+		{
+			tracing::debug!(key = ?key, "subscription started");
+		}
+
+		let mut stream = pin!(inner.fetcher.subscribe::<T>(&key));
+		loop {
+			let Some(res) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await else {
+				break;
+			};
+
+			let mut states = inner.cache.states();
+			states.mutate(slot, |state| {
+				match res {
+					Ok(data) => {
+						state.insert_fetched(Arc::new(data));
+					}
+					Err(err) => {
+						#[cfg(feature = "tracing")]
+						{
+							tracing::info!(key = ?key, "subscription ERR: {err}");
+						}
+
+						state.insert_error(Arc::new(err));
+					}
+				}
+				inner.hook.request_redraw();
+			});
+		}
+
+		#[cfg(feature = "tracing")]
+		{
+			tracing::debug!(key = ?key, "subscription ended");
+		}
+	});
+	if did_launch {
+		let status = entry.status();
+		if !status.get(CacheEntryStatus::HAS_DATA, Ordering::Relaxed) {
+			status.set(CacheEntryStatus::LOADING, Ordering::Relaxed);
+		}
+	}
+}
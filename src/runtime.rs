@@ -8,6 +8,7 @@
 
 use std::{future::Future, time::Duration};
 
+#[allow(dead_code)] // only used when zero or multiple runtime features are enabled
 mod null;
 #[cfg(feature = "smol")]
 mod smol;
@@ -83,4 +84,13 @@ pub trait Task<T>: Send + Sync + 'static {
 	/// Returns `true` if the task is no longer running, either due to normal completion or abortion via
 	/// [`Task::abort`].
 	fn is_finished(&self) -> bool;
+
+	/// Lets the task keep running in the background after this handle is dropped.
+	///
+	/// Only runtimes whose tasks are cancelled when their handle is dropped need to override this.
+	fn detach(self)
+	where
+		Self: Sized
+	{
+	}
 }
@@ -31,4 +31,8 @@ impl<T: Send + 'static> super::Task<T> for Task<T> {
 	fn is_finished(&self) -> bool {
 		Task::<T>::is_finished(self)
 	}
+
+	fn detach(self) {
+		Task::<T>::detach(self);
+	}
 }
@@ -12,9 +12,9 @@ use std::{
 use tokio::{task::yield_now, time::advance};
 
 use crate::{
-	CacheEntryStatus, MutateOptions, Options, Persisted, SWR,
+	CacheEntryStatus, MutateOptions, Options, Persisted, RetryPolicy, SWR,
 	cache::CacheEntry,
-	fetcher::mock::{Fetcher, Key},
+	fetcher::mock::{Error, Fetcher, Key},
 	hook::MockHook,
 	runtime::Tokio
 };
@@ -138,6 +138,46 @@ async fn refresh() {
 	assert_eq!(fetcher.fetch_count(), 3);
 }
 
+#[tokio::test(start_paused = true)]
+async fn refresh_after_superseded_fetch() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let key = Key::Delayed(Duration::from_secs(2));
+	let persisted = swr.persisted::<usize, _>(
+		&key,
+		Options {
+			refresh_interval: Some(Duration::from_secs(5)),
+			..Options::immutable()
+		}
+	);
+	hook.set_focused(true);
+	hook.within(|| persisted.get());
+	yield_now().await;
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	// mutate while the refresh is in flight, discarding its result
+	advance(Duration::from_secs(5)).await;
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+	persisted.mutate(Arc::new(7));
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	assert_eq!(persisted.get_shallow().data.as_deref(), Some(&7));
+
+	// refreshing continues as usual
+	for expected_count in 3..6 {
+		advance(Duration::from_secs(5)).await;
+		yield_now().await;
+		assert_eq!(fetcher.fetch_count(), expected_count);
+		advance(Duration::from_secs(2)).await;
+		yield_now().await;
+	}
+}
+
 #[tokio::test(start_paused = true)]
 async fn retry() {
 	let hook = MockHook::default();
@@ -179,6 +219,422 @@ async fn retry() {
 	assert_eq!(fetcher.fetch_count(), 4);
 }
 
+#[tokio::test(start_paused = true)]
+async fn retry_policy() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	// exponential: retries after 3s, 6s, then 12s
+	let key = Key::ErrorNTimes(3);
+	hook.within(|| {
+		swr.get_with::<usize, _>(
+			&key,
+			Options {
+				error_retry_interval: Some(Duration::from_secs(3)),
+				error_retry_policy: RetryPolicy::Exponential,
+				..Options::immutable()
+			}
+		);
+		// the first non-constant policy is kept when merging
+		swr.get_with::<usize, _>(
+			&key,
+			Options {
+				error_retry_policy: RetryPolicy::Custom(Arc::new(|_, _: &(dyn std::error::Error + 'static)| None)),
+				..Options::immutable()
+			}
+		);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	for (delay, expected_count) in [(3, 2), (6, 3), (12, 4)] {
+		advance(Duration::from_secs(delay - 1)).await;
+		yield_now().await;
+		assert_eq!(fetcher.fetch_count(), expected_count - 1);
+		advance(Duration::from_secs(1)).await;
+		yield_now().await;
+		assert_eq!(fetcher.fetch_count(), expected_count);
+	}
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data, Some(Arc::new(42)));
+
+	// custom: give up after the second attempt
+	let key = Key::AlwaysError;
+	hook.within(|| {
+		swr.get_with::<usize, _>(
+			&key,
+			Options {
+				error_retry_interval: Some(Duration::from_secs(3)),
+				error_retry_policy: RetryPolicy::Custom(Arc::new(|attempt, _: &(dyn std::error::Error + 'static)| {
+					(attempt < 1).then_some(Duration::from_secs(3))
+				})),
+				..Options::immutable()
+			}
+		);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 5);
+	for _ in 0..3 {
+		advance(Duration::from_secs(3)).await;
+		yield_now().await;
+	}
+	assert_eq!(fetcher.fetch_count(), 6);
+}
+
+#[tokio::test(start_paused = true)]
+async fn callbacks() {
+	use crate::fetcher::mock::Error;
+
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let successes = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let slow = Arc::new(std::sync::Mutex::new(Vec::new()));
+	// the same options are shared by every key, so the callbacks need the key to tell them apart
+	let options = || {
+		let (successes, errors, slow) = (Arc::clone(&successes), Arc::clone(&errors), Arc::clone(&slow));
+		Options {
+			error_retry_interval: None,
+			loading_timeout: Some(Duration::from_secs(1)),
+			..Options::immutable()
+		}
+		.with_on_success(move |key: &Key, data: &Arc<usize>| successes.lock().unwrap().push((*key, **data)))
+		.with_on_error(move |key: &Key, err| {
+			assert!(err.is::<Error>());
+			errors.lock().unwrap().push(*key);
+		})
+		.with_on_loading_slow(move |key: &Key| slow.lock().unwrap().push(*key))
+	};
+
+	// register the same callbacks over multiple frames; they should only be invoked once per fetch
+	for _ in 0..3 {
+		hook.within(|| {
+			swr.get_all_with::<usize, _, _>(&[Key::Basic, Key::AlwaysError, Key::Delayed(Duration::from_secs(2))], options());
+		});
+		yield_now().await;
+	}
+	assert_eq!(*successes.lock().unwrap(), vec![(Key::Basic, 42)]);
+	assert_eq!(*errors.lock().unwrap(), vec![Key::AlwaysError]);
+	assert!(slow.lock().unwrap().is_empty());
+
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert_eq!(*slow.lock().unwrap(), vec![Key::Delayed(Duration::from_secs(2))]);
+
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert_eq!(successes.lock().unwrap().len(), 2);
+	assert_eq!(slow.lock().unwrap().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+#[should_panic = "were registered to a cache whose keys are"]
+async fn callbacks_with_mismatched_key() {
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	let options = Options::immutable().with_on_success(|_: &String, _: &Arc<usize>| {});
+	swr.persisted::<usize, _>(&Key::Basic, options);
+}
+
+#[tokio::test(start_paused = true)]
+async fn callbacks_are_scoped_to_usage() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let successes = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let options = |usage: &'static str| {
+		let successes = Arc::clone(&successes);
+		Options::immutable().with_on_success(move |_: &Key, _: &Arc<usize>| successes.lock().unwrap().push(usage))
+	};
+
+	// both closures have the same type, but belong to different usages
+	let a = swr.persisted::<usize, _>(&Key::Basic, options("a"));
+	let b = swr.persisted::<usize, _>(&Key::Basic, options("b"));
+	hook.within(|| {
+		a.get();
+		b.get();
+	});
+	yield_now().await;
+	assert_eq!(*successes.lock().unwrap(), vec!["a", "b"]);
+
+	drop(b);
+	successes.lock().unwrap().clear();
+	a.revalidate();
+	hook.within(|| {
+		a.get();
+	});
+	yield_now().await;
+	assert_eq!(*successes.lock().unwrap(), vec!["a"]);
+
+	// frame usages last until the end of the next frame in which they aren't registered again
+	drop(a);
+	hook.within(|| {
+		swr.get_with::<usize, _>(&Key::Basic, options("c"));
+	});
+	successes.lock().unwrap().clear();
+	swr.revalidate(&Key::Basic);
+	hook.within(|| {
+		swr.get_with::<usize, _>(&Key::Basic, options("c"));
+	});
+	yield_now().await;
+	assert_eq!(*successes.lock().unwrap(), vec!["c"]);
+
+	successes.lock().unwrap().clear();
+	swr.revalidate(&Key::Basic);
+	hook.within(|| {
+		swr.get::<usize, _>(&Key::Basic);
+	});
+	yield_now().await;
+	assert!(successes.lock().unwrap().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn middleware() {
+	use crate::{
+		FetchContext, FetchIntent, Fetched, Fetcher as _, FetcherExt, Middleware,
+		fetcher::mock::Error,
+		layer::{ConcurrencyLimit, Timeout}
+	};
+
+	#[derive(Default)]
+	struct Recorder(parking_lot::Mutex<Vec<(Key, FetchIntent)>>);
+
+	impl Middleware<Fetcher> for Arc<Recorder> {
+		async fn fetch<T: serde::de::DeserializeOwned + Send + Sync + 'static>(
+			&self,
+			inner: &Fetcher,
+			key: &Key,
+			context: &FetchContext
+		) -> Result<Fetched<T>, Error> {
+			self.0.lock().push((*key, context.intent()));
+			match key {
+				// short-circuit
+				Key::Page(0) => serde_json::from_str("7").map(Fetched::Data).map_err(|_| Error),
+				_ => inner.fetch_with_context::<T>(key, context).await
+			}
+		}
+	}
+
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let recorder = Arc::new(Recorder::default());
+	let limit = ConcurrencyLimit::new(1);
+	let swr = SWR::new_in(
+		fetcher
+			.clone()
+			.layer(Arc::clone(&recorder))
+			.layer(Timeout::new(Duration::from_secs(3), Tokio))
+			.layer(limit.clone()),
+		Tokio,
+		hook.clone()
+	);
+
+	let options = || Options {
+		error_retry_interval: None,
+		..Options::immutable()
+	};
+	hook.within(|| {
+		swr.get_with::<usize, _>(&Key::Page(0), options());
+		swr.get_with::<usize, _>(&Key::Delayed(Duration::from_secs(2)), options());
+		swr.get_with::<usize, _>(&Key::Delayed(Duration::from_secs(4)), options());
+	});
+	yield_now().await;
+
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Page(0)).unwrap().data, Some(Arc::new(7)));
+	assert_eq!(recorder.0.lock()[0], (Key::Page(0), FetchIntent::FIRST_USAGE));
+	// only one delayed fetch may run at once
+	assert_eq!(fetcher.fetch_count(), 1);
+	assert_eq!(limit.active(), 1);
+
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Delayed(Duration::from_secs(2))).unwrap().data, Some(Arc::new(42)));
+	assert_eq!(fetcher.fetch_count(), 2);
+
+	// the second delayed fetch exceeds the timeout
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	assert!(
+		swr.get_shallow::<usize, _>(&Key::Delayed(Duration::from_secs(4)))
+			.unwrap()
+			.error
+			.is_some()
+	);
+	assert_eq!(limit.active(), 0);
+
+	swr.revalidate(&Key::Page(0));
+	hook.within(|| {
+		swr.get_with::<usize, _>(&Key::Page(0), options());
+	});
+	yield_now().await;
+	assert_eq!(recorder.0.lock().last(), Some(&(Key::Page(0), FetchIntent::MANUAL)));
+
+	// fetching with just an intent still goes through the middleware
+	let layered = fetcher.clone().layer(Arc::clone(&recorder));
+	assert_eq!(layered.fetch_with_intent::<usize>(&Key::Page(0), FetchIntent::FOCUS).await.unwrap(), 7);
+	assert_eq!(recorder.0.lock().last(), Some(&(Key::Page(0), FetchIntent::FOCUS)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn fetch_context() {
+	use crate::{FetchContext, FetchIntent, Fetched, Fetcher as _, FetcherExt, Middleware, fetcher::mock::Error};
+
+	#[derive(Default)]
+	struct Recorder(parking_lot::Mutex<Vec<FetchContext>>);
+
+	impl Middleware<Fetcher> for Arc<Recorder> {
+		async fn fetch<T: serde::de::DeserializeOwned + Send + Sync + 'static>(
+			&self,
+			inner: &Fetcher,
+			key: &Key,
+			context: &FetchContext
+		) -> Result<Fetched<T>, Error> {
+			self.0.lock().push(context.clone());
+			inner.fetch_with_context::<T>(key, context).await
+		}
+	}
+
+	let hook = MockHook::default();
+	let recorder = Arc::new(Recorder::default());
+	let swr = SWR::new_in(Fetcher::new().layer(Arc::clone(&recorder)), Tokio, hook.clone());
+
+	let options = || Options {
+		error_retry_interval: Some(Duration::from_secs(3)),
+		..Options::immutable()
+	};
+	let key = Key::ErrorNTimes(1);
+	hook.within(|| {
+		swr.get_with::<usize, _>(&key, options());
+	});
+	yield_now().await;
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+
+	{
+		let contexts = recorder.0.lock();
+		assert_eq!(contexts.len(), 2);
+		assert_eq!((contexts[0].intent(), contexts[0].attempt()), (FetchIntent::FIRST_USAGE, 0));
+		assert_eq!((contexts[1].intent(), contexts[1].attempt()), (FetchIntent::RETRY, 1));
+		assert!(contexts.iter().all(|c| c.previous_data_age().is_none()));
+	}
+
+	advance(Duration::from_secs(10)).await;
+	swr.revalidate(&key);
+	hook.within(|| {
+		swr.get_with::<usize, _>(&key, options());
+	});
+	yield_now().await;
+	{
+		let contexts = recorder.0.lock();
+		assert_eq!((contexts[2].intent(), contexts[2].attempt()), (FetchIntent::MANUAL, 0));
+		assert_eq!(contexts[2].previous_data_age(), Some(Duration::from_secs(10)));
+	}
+
+	// mutations abort in-flight fetches
+	let key = Key::Delayed(Duration::from_secs(5));
+	hook.within(|| {
+		swr.get_with::<usize, _>(&key, options());
+	});
+	yield_now().await;
+	let cancellation = recorder.0.lock()[3].cancellation().clone();
+	assert!(!cancellation.is_cancelled());
+	swr.mutate_with(&key, MutateOptions::default(), |_, _| async move { Ok::<_, Infallible>(Arc::new(1usize)) })
+		.await
+		.unwrap()
+		.unwrap();
+	assert!(cancellation.is_cancelled());
+
+	// the age of the data isn't affected by failed fetches
+	let key = Key::AlwaysError;
+	swr.mutate(&key, Arc::new(1usize));
+	for _ in 0..2 {
+		advance(Duration::from_secs(5)).await;
+		swr.revalidate(&key);
+		hook.within(|| {
+			swr.get_with::<usize, _>(&key, options());
+		});
+		yield_now().await;
+	}
+	assert_eq!(recorder.0.lock().last().unwrap().previous_data_age(), Some(Duration::from_secs(10)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn not_modified() {
+	use std::sync::atomic::AtomicUsize;
+
+	use crate::{FetchContext, Fetched, Validators};
+
+	/// Serves a resource whose value is its version.
+	#[derive(Default)]
+	struct VersionedFetcher {
+		version: AtomicUsize,
+		fetch_count: AtomicUsize
+	}
+
+	impl crate::Fetcher for Arc<VersionedFetcher> {
+		type Response<T: Send + Sync + 'static> = T;
+		type Error = Infallible;
+		type Key = Key;
+
+		async fn fetch<T: serde::de::DeserializeOwned + Send + Sync + 'static>(&self, _: &Key) -> Result<T, Infallible> {
+			self.fetch_count.fetch_add(1, Ordering::AcqRel);
+			Ok(serde_json::from_value(serde_json::json!(self.version.load(Ordering::Acquire))).unwrap())
+		}
+
+		async fn fetch_with_context<T: serde::de::DeserializeOwned + Send + Sync + 'static>(
+			&self,
+			key: &Key,
+			context: &FetchContext
+		) -> Result<Fetched<T>, Infallible> {
+			let version = self.version.load(Ordering::Acquire);
+			if context.validators().and_then(|v| v.downcast_ref::<usize>()) == Some(&version) {
+				self.fetch_count.fetch_add(1, Ordering::AcqRel);
+				return Ok(Fetched::NotModified);
+			}
+			self.fetch::<T>(key).await.map(Fetched::Data)
+		}
+
+		fn validators<T: Send + Sync + 'static>(&self, _: &Key, _: &T) -> Option<Validators> {
+			Some(Validators::new(self.version.load(Ordering::Acquire)))
+		}
+	}
+
+	let hook = MockHook::default();
+	let fetcher = Arc::new(VersionedFetcher::default());
+	let swr = SWR::new_in(Arc::clone(&fetcher), Tokio, hook.clone());
+
+	let options = || Options {
+		refresh_interval: Some(Duration::from_secs(5)),
+		refresh_when_unfocused: true,
+		..Options::immutable()
+	};
+	let first = swr.persisted::<usize, _>(&Key::Basic, options());
+	hook.within(|| first.get());
+	yield_now().await;
+	let data = first.get_shallow().data.unwrap();
+	assert_eq!(*data, 0);
+
+	// unchanged; the data is kept as-is, and the refresh is still rescheduled
+	for i in 2..4 {
+		hook.take_wants_redraw();
+		advance(Duration::from_secs(5)).await;
+		yield_now().await;
+		assert_eq!(fetcher.fetch_count.load(Ordering::Acquire), i);
+		assert!(hook.take_wants_redraw());
+		assert!(Arc::ptr_eq(&first.get_shallow().data.unwrap(), &data));
+		assert!(first.get_shallow().error.is_none());
+	}
+
+	fetcher.version.store(1, Ordering::Release);
+	advance(Duration::from_secs(5)).await;
+	yield_now().await;
+	assert_eq!(first.get_shallow().data, Some(Arc::new(1)));
+}
+
 #[tokio::test(start_paused = true)]
 async fn drop_values() {
 	static DATA_DROP_FLAG: AtomicBool = AtomicBool::new(false);
@@ -272,4 +728,1255 @@ async fn mutate() {
 		assert_eq!(entry.data().unwrap().unwrap(), Arc::new(48usize));
 	})
 	.unwrap();
+
+	// both the mutator and the populator are given the data from before the mutation
+	let _ = swr
+		.mutate_with(
+			&Key::Basic,
+			MutateOptions::default().with_populator(|ret: &mut usize, old: Option<&Arc<usize>>| Arc::new(*ret + **old.unwrap())),
+			|old: Option<&Arc<usize>>, _fetcher| {
+				let old = **old.unwrap();
+				async move { Ok::<_, usize>(old * 2) }
+			}
+		)
+		.await;
+
+	inspect_entry(&swr, Key::Basic, |entry| {
+		assert_eq!(entry.data().unwrap().unwrap(), Arc::new(48usize * 3));
+	})
+	.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn superseded_fetch() {
+	use std::sync::atomic::AtomicUsize;
+
+	use crate::{
+		revalidate::{RevalidateIntent, launch_fetch},
+		util::TaskStartMode
+	};
+
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	// a fetch which started before a mutation must not overwrite it
+	let key = Key::Delayed(Duration::from_secs(1));
+	hook.within(|| {
+		let _ = swr.get_with::<usize, _>(&key, Options::immutable());
+	});
+	swr.mutate(&key, Arc::new(47usize));
+	yield_now().await;
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	inspect_entry(&swr, key, |entry| {
+		assert_eq!(entry.fetch_count, 1);
+		assert_eq!(entry.data().unwrap().unwrap(), Arc::new(47usize));
+	})
+	.unwrap();
+
+	/// Each fetch resolves to its number, with later fetches resolving sooner.
+	#[derive(Default)]
+	struct RacingFetcher(AtomicUsize);
+
+	impl crate::Fetcher for Arc<RacingFetcher> {
+		type Response<T: Send + Sync + 'static> = T;
+		type Error = Error;
+		type Key = Key;
+
+		async fn fetch<T: serde::de::DeserializeOwned + Send + Sync + 'static>(&self, _: &Key) -> Result<T, Error> {
+			let n = self.0.fetch_add(1, Ordering::AcqRel) + 1;
+			tokio::time::sleep(Duration::from_secs(3 - n as u64)).await;
+			Ok(serde_json::from_value(serde_json::json!(n)).unwrap())
+		}
+	}
+
+	let swr = SWR::new_in(Arc::new(RacingFetcher::default()), Tokio, hook.clone());
+	hook.within(|| {
+		let _ = swr.get_with::<usize, _>(&Key::Basic, Options::immutable());
+	});
+	let slot = swr.cache().get(&Key::Basic).unwrap();
+	swr.cache().states().mutate(slot, |state| {
+		launch_fetch::<usize, _, _>(state, &swr.inner, slot, TaskStartMode::Override, RevalidateIntent::MANUALLY_TRIGGERED);
+	});
+
+	yield_now().await;
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.as_deref(), Some(&2));
+
+	// the first fetch finishes last, but is older than the data
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.as_deref(), Some(&2));
+	assert_eq!(swr.cache().states().get(slot).unwrap().fetch_count, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn infinite() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let mut infinite = swr.infinite::<usize, _>(Options::immutable(), |index, previous| {
+		assert_eq!(previous.copied(), index.checked_sub(1));
+		(index < 3).then_some(Key::Page(index))
+	});
+
+	hook.within(|| {
+		let res = infinite.get();
+		assert!(res.loading);
+		assert!(res.pages.is_empty());
+	});
+	yield_now().await;
+
+	infinite.load_more();
+	hook.within(|| {
+		let res = infinite.get();
+		assert!(res.loading);
+		assert_eq!(res.pages, vec![Arc::new(0)]);
+	});
+	yield_now().await;
+
+	infinite.set_size(5);
+	for _ in 0..2 {
+		hook.within(|| infinite.get());
+		yield_now().await;
+	}
+	hook.within(|| {
+		let res = infinite.get();
+		assert!(!res.loading);
+		assert!(res.reached_end);
+		assert_eq!(res.pages, vec![Arc::new(0), Arc::new(1), Arc::new(2)]);
+	});
+	assert_eq!(fetcher.fetch_count(), 3);
+
+	infinite.revalidate();
+	hook.within(|| {
+		let res = infinite.get();
+		assert!(res.validating);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 6);
+
+	// only the first page should revalidate on focus
+	advance(Duration::from_secs(5)).await;
+	hook.set_focus_triggered(true);
+	hook.within(|| infinite.get());
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 7);
+}
+
+#[tokio::test(start_paused = true)]
+async fn subscription() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let persisted = swr.subscription::<usize, _>(
+		&Key::Basic,
+		Options {
+			garbage_collect_timeout: Some(Duration::from_secs(5)),
+			..Options::default()
+		}
+	);
+	// subscription shouldn't start until the key is used
+	assert_eq!(fetcher.push(1), 0);
+
+	hook.within(|| {
+		let res = persisted.get();
+		assert!(res.loading);
+	});
+	yield_now().await;
+
+	for value in [1, 2, 3] {
+		assert_eq!(fetcher.push(value), 1);
+		yield_now().await;
+		assert!(hook.take_wants_redraw());
+		hook.within(|| {
+			let res = persisted.get();
+			assert!(!res.loading);
+			assert_eq!(res.data, Some(Arc::new(value)));
+		});
+	}
+	assert_eq!(fetcher.fetch_count(), 0);
+
+	drop(persisted);
+	hook.end_frame();
+	advance(Duration::from_secs(5)).await;
+	hook.end_frame();
+	assert!(inspect_entry(&swr, Key::Basic, |_| {}).is_none());
+
+	yield_now().await;
+	assert_eq!(fetcher.push(4), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn conditional() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	hook.within(|| {
+		let res = swr.get_when::<usize, Key>(None);
+		assert!(!res.loading);
+		assert!(res.data.is_none() && res.error.is_none());
+
+		let res = swr.get_dependent::<usize, _, _>(|| res.data.map(|n| Key::Page(*n)), Options::immutable());
+		assert!(!res.loading);
+
+		let res = swr.get_when::<usize, _>(Some(&Key::Basic));
+		assert!(res.loading);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	hook.within(|| {
+		let res = swr.get::<usize, _>(&Key::Basic);
+		let res = swr.get_dependent::<usize, _, _>(|| res.data.map(|n| Key::Page(*n)), Options::immutable());
+		assert!(res.loading);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn disabled() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let disabled = || Options {
+		enabled: false,
+		..Options::immutable()
+	};
+
+	for _ in 0..2 {
+		hook.within(|| {
+			let res = swr.get_with::<usize, _>(&Key::Basic, disabled());
+			assert!(!res.loading);
+		});
+		yield_now().await;
+	}
+	assert_eq!(fetcher.fetch_count(), 0);
+
+	swr.revalidate(&Key::Basic);
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, disabled());
+		assert!(res.loading);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Page(1), disabled());
+		assert!(!res.loading);
+	});
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Page(1), Options::immutable());
+		assert!(res.loading);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn predicate_operations() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let is_page = |key: &Key| matches!(key, Key::Page(_));
+
+	hook.within(|| {
+		for key in [Key::Basic, Key::Page(1), Key::Page(2)] {
+			let _ = swr.get_with::<usize, _>(&key, Options::immutable());
+		}
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 3);
+
+	hook.take_wants_redraw();
+	// the mutator may use the cache itself
+	let mutated = swr.mutate_where::<usize, _, _>(is_page, |key, data| {
+		assert_eq!(swr.get_shallow::<usize, _>(key).unwrap().data.as_ref(), data);
+		Arc::new(**data.unwrap() * 10)
+	});
+	assert_eq!(mutated, 2);
+	assert!(hook.take_wants_redraw());
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Page(2)).unwrap().data, Some(Arc::new(20)));
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data, Some(Arc::new(42)));
+
+	assert_eq!(swr.revalidate_where(is_page), 2);
+	hook.within(|| {
+		for key in [Key::Basic, Key::Page(1), Key::Page(2)] {
+			let _ = swr.get_with::<usize, _>(&key, Options::immutable());
+		}
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 5);
+
+	assert_eq!(swr.invalidate_where(|key| *key == Key::Page(1)), 1);
+	let res = swr.get_shallow::<usize, _>(&Key::Page(1)).unwrap();
+	assert!(res.data.is_none());
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Page(1), Options::immutable());
+		assert!(res.loading);
+	});
+	// entries which are still loading have nothing to invalidate, but their fetch is restarted on next use
+	assert_eq!(swr.invalidate_where(|key| *key == Key::Page(1)), 0);
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Page(1), Options::immutable());
+		assert!(res.loading);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 6);
+
+	assert_eq!(swr.revalidate_where(|_| false), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn tags() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let render = || {
+		hook.within(|| {
+			for key in [Key::Basic, Key::Page(1), Key::Page(2)] {
+				let _ = swr.get_with::<usize, _>(
+					&key,
+					Options {
+						tags: vec!["all".into()],
+						garbage_collect_timeout: Some(Duration::from_secs(5)),
+						..Options::immutable()
+					}
+				);
+			}
+		})
+	};
+
+	render();
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 3);
+
+	// `page:1` is attached by the fetcher
+	assert_eq!(swr.invalidate_tags(&["page:1"]), 1);
+	render();
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 4);
+
+	assert_eq!(swr.invalidate_tags(&["all", "page:2"]), 3);
+	render();
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 7);
+
+	// entries which aren't in use shouldn't be revalidated, and should be removed from the index once collected
+	hook.end_frame();
+	assert_eq!(swr.invalidate_tags(&["all"]), 0);
+	advance(Duration::from_secs(5)).await;
+	hook.end_frame();
+	assert!(swr.cache().slots_tagged(&["all", "page:1", "page:2"]).is_empty());
+
+	// tags attached by the fetcher are replaced on each fetch, but tags attached by usages are kept
+	fetcher.set_tags(vec!["all".into(), "first".into()]);
+	render();
+	yield_now().await;
+	assert_eq!(swr.cache().slots_tagged(&["first"]).len(), 1);
+
+	fetcher.set_tags(vec!["second".into()]);
+	swr.revalidate(&Key::Basic);
+	render();
+	yield_now().await;
+	assert!(swr.cache().slots_tagged(&["first"]).is_empty());
+	assert_eq!(swr.cache().slots_tagged(&["second"]).len(), 1);
+	assert_eq!(swr.cache().slots_tagged(&["all"]).len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn dedupe() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let options = || Options {
+		dedupe_interval: Some(Duration::from_secs(2)),
+		garbage_collect_timeout: None,
+		..Options::default()
+	};
+
+	hook.within(|| swr.get_with::<usize, _>(&Key::Basic, options()));
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	// key falls out of use & comes back within the dedupe interval; stale revalidation should be dropped
+	hook.end_frame();
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, options());
+		assert!(!res.validating);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	// manual revalidation is never deduplicated
+	swr.revalidate(&Key::Basic);
+	hook.within(|| swr.get_with::<usize, _>(&Key::Basic, options()));
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+
+	// mutations don't count towards the dedupe interval
+	advance(Duration::from_secs(2)).await;
+	swr.mutate(&Key::Basic, Arc::new(47));
+	hook.end_frame();
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, options());
+		assert!(res.validating);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stale_time() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let options = || Options {
+		stale_time: Some(Duration::from_secs(10)),
+		garbage_collect_timeout: None,
+		..Options::default()
+	};
+
+	let res = hook.within(|| swr.get_with::<usize, _>(&Key::Basic, options()));
+	assert!(!res.is_stale());
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	// key falls out of use & comes back while fresh, then the application is focused
+	advance(Duration::from_secs(6)).await;
+	hook.end_frame();
+	hook.set_focus_triggered(true);
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, options());
+		assert!(!res.validating);
+		assert!(!res.is_stale());
+	});
+	hook.set_focus_triggered(false);
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 1);
+
+	// once stale, the key is revalidated when it comes back into use
+	advance(Duration::from_secs(5)).await;
+	assert!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().is_stale());
+	hook.end_frame();
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, options());
+		assert!(res.validating);
+	});
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+	assert!(!swr.get_shallow::<usize, _>(&Key::Basic).unwrap().is_stale());
+}
+
+#[tokio::test(start_paused = true)]
+async fn timing_metadata() {
+	use crate::{FetchIntent, util::Instant};
+
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let options = || Options {
+		error_retry_interval: Some(Duration::from_secs(3)),
+		..Options::immutable()
+	};
+
+	let start = Instant::now();
+	let key = Key::ErrorNTimes(1);
+	let res = hook.within(|| swr.get_with::<usize, _>(&key, options()));
+	assert_eq!(res.fetch_intent, Some(FetchIntent::FIRST_USAGE));
+	assert_eq!(res.fetch_count, 0);
+	yield_now().await;
+
+	let res = swr.get_shallow::<usize, _>(&key).unwrap();
+	assert_eq!((res.fetch_count, res.failure_count), (1, 1));
+	assert_eq!(res.error_updated_at, Some(start));
+	assert_eq!(res.data_updated_at, None);
+	assert_eq!(res.fetch_intent, None);
+
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	let res = swr.get_shallow::<usize, _>(&key).unwrap();
+	assert_eq!((res.fetch_count, res.failure_count), (2, 0));
+	assert_eq!(res.data_updated_at, Some(start + Duration::from_secs(3)));
+	assert_eq!(res.error_updated_at, Some(start));
+
+	let key = Key::Delayed(Duration::from_secs(2));
+	hook.within(|| swr.get_with::<usize, _>(&key, options()));
+	yield_now().await;
+	// the fetch is still in progress after a mutation, even though its result will be discarded
+	swr.mutate(&key, Arc::new(1usize));
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().fetch_intent, Some(FetchIntent::FIRST_USAGE));
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	let res = swr.get_shallow::<usize, _>(&key).unwrap();
+	assert_eq!(res.last_fetch_duration, Some(Duration::from_secs(2)));
+	assert_eq!(res.fetch_intent, None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn fetch_status() {
+	use crate::{FetchStatus, Settled, util::Instant};
+
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let options = || Options {
+		error_retry_interval: Some(Duration::from_secs(3)),
+		error_retry_count: Some(NonZeroU8::new(1).unwrap()),
+		..Options::immutable()
+	};
+
+	let res = hook.within(|| swr.get_with::<usize, _>(&Key::Basic, Options { enabled: false, ..options() }));
+	assert_eq!(res.status, FetchStatus::Paused);
+
+	let res = hook.within(|| swr.get_with::<usize, _>(&Key::Basic, options()));
+	assert_eq!(res.status, FetchStatus::Loading);
+	assert!(res.loading);
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().status, FetchStatus::Success);
+
+	swr.revalidate(&Key::Basic);
+	let res = hook.within(|| swr.get_with::<usize, _>(&Key::Basic, options()));
+	assert_eq!(res.status, FetchStatus::Refreshing { previous: Settled::Success });
+	assert!(res.validating);
+	yield_now().await;
+
+	let start = Instant::now();
+	hook.within(|| swr.get_with::<usize, _>(&Key::AlwaysError, options()));
+	yield_now().await;
+	let res = swr.get_shallow::<usize, _>(&Key::AlwaysError).unwrap();
+	assert_eq!(res.status, FetchStatus::RetryScheduled { at: start + Duration::from_secs(3) });
+	assert!(res.status.is_error());
+
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::AlwaysError).unwrap().status, FetchStatus::Error);
+}
+
+#[tokio::test(start_paused = true)]
+async fn selector() {
+	use std::sync::atomic::AtomicUsize;
+
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	let runs = Arc::new(AtomicUsize::new(0));
+	let mut selector = swr.persisted::<usize, _>(&Key::Basic, Options::immutable()).selector({
+		let runs = Arc::clone(&runs);
+		move |n| {
+			runs.fetch_add(1, Ordering::Relaxed);
+			n % 2 == 0
+		}
+	});
+
+	assert_eq!(hook.within(|| selector.get().copied()), None);
+	assert!(!selector.changed());
+	yield_now().await;
+
+	assert_eq!(hook.within(|| selector.get().copied()), Some(true));
+	assert!(selector.changed());
+	assert_eq!(hook.within(|| selector.get().copied()), Some(true));
+	assert!(!selector.changed());
+	// the data hasn't changed, so the selector only ran once
+	assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+	// new data with the same selected value
+	selector.persisted().mutate(Arc::new(44));
+	assert_eq!(selector.get_shallow().copied(), Some(true));
+	assert!(!selector.changed());
+	assert_eq!(runs.load(Ordering::Relaxed), 2);
+
+	selector.persisted().mutate(Arc::new(45));
+	assert_eq!(selector.get_shallow().copied(), Some(false));
+	assert!(selector.changed());
+	assert_eq!(runs.load(Ordering::Relaxed), 3);
+
+	let res = swr.get_shallow::<usize, _>(&Key::Basic).unwrap();
+	assert_eq!(res.select(|n| n + 1), Some(46));
+}
+
+#[tokio::test(start_paused = true)]
+async fn combine() {
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	let keys = [Key::Page(1), Key::Page(2), Key::Delayed(Duration::from_secs(1))];
+	let res = hook.within(|| swr.get_all::<usize, _, _>(&keys));
+	assert!(res.loading);
+	assert!(res.data.is_none());
+	for key in keys {
+		inspect_entry(&swr, key, |entry| {
+			assert!(entry.status().get(CacheEntryStatus::ALIVE, Ordering::Acquire));
+		})
+		.unwrap();
+	}
+
+	yield_now().await;
+	// the delayed key is still loading
+	let res = hook.within(|| swr.get_all::<usize, _, _>(&keys));
+	assert!(res.loading);
+	assert!(res.data.is_none());
+
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	let res = hook.within(|| swr.get_all::<usize, _, _>(&keys));
+	assert!(!res.loading);
+	assert_eq!(res.data.unwrap().iter().map(|n| **n).collect::<Vec<_>>(), [1, 2, 42]);
+
+	let persisted = swr.persisted::<usize, _>(&Key::AlwaysError, Options::immutable());
+	let res = hook.within(|| crate::combine!(swr.get::<usize, _>(&Key::Basic), persisted.get()));
+	assert!(res.loading);
+	yield_now().await;
+	let res = hook.within(|| crate::combine!(swr.get::<usize, _>(&Key::Basic), persisted.get()));
+	assert!(res.error.is_some());
+	assert!(res.data.is_none());
+
+	persisted.mutate(Arc::new(7));
+	let res = hook.within(|| crate::combine!(swr.get::<usize, _>(&Key::Basic), persisted.get()));
+	let (a, b) = res.data.unwrap();
+	assert_eq!((*a, *b), (42, 7));
+}
+
+#[tokio::test(start_paused = true)]
+async fn keep_previous_data() {
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	let strong_count = |key| inspect_entry(&swr, key, |entry| entry.strong_count.load(Ordering::Acquire)).unwrap();
+
+	let mut persisted = swr.persisted::<usize, _>(&Key::Page(1), Options::immutable());
+	hook.within(|| persisted.get());
+	yield_now().await;
+
+	persisted.set_key(&Key::Delayed(Duration::from_secs(1)));
+	assert_eq!(strong_count(Key::Page(1)), 0);
+	assert_eq!(strong_count(Key::Delayed(Duration::from_secs(1))), 1);
+
+	let res = hook.within(|| persisted.get());
+	assert!(res.loading);
+	assert!(res.is_previous_data());
+	assert_eq!(res.data.as_deref(), Some(&1));
+
+	// the previous data is kept through keys which never loaded
+	persisted.set_key(&Key::Delayed(Duration::from_secs(2)));
+	assert_eq!(strong_count(Key::Delayed(Duration::from_secs(1))), 0);
+	let res = hook.within(|| persisted.get());
+	assert!(res.is_previous_data());
+	assert_eq!(res.data.as_deref(), Some(&1));
+
+	yield_now().await;
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	let res = hook.within(|| persisted.get());
+	assert!(!res.is_previous_data());
+	assert_eq!(res.data.as_deref(), Some(&42));
+
+	// once the current key has had data of its own, the previous data is never shown again
+	assert_eq!(swr.invalidate_where(|key| *key == Key::Delayed(Duration::from_secs(2))), 1);
+	let res = persisted.get_shallow();
+	assert!(!res.is_previous_data());
+	assert_eq!(res.data, None);
+
+	drop(persisted);
+	assert_eq!(strong_count(Key::Delayed(Duration::from_secs(2))), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn debounce() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+
+	let mut debounced = swr.debounced::<usize>(Duration::from_millis(300), Options::immutable());
+	debounced.set_key(&Key::Page(1));
+	let res = hook.within(|| debounced.get());
+	assert!(!res.loading);
+	assert!(debounced.is_pending());
+
+	// typing more before the delay elapses restarts it; intermediate keys are never created
+	yield_now().await;
+	advance(Duration::from_millis(200)).await;
+	debounced.set_key(&Key::Page(2));
+	yield_now().await;
+	advance(Duration::from_millis(200)).await;
+	debounced.set_key(&Key::Page(2));
+	hook.within(|| debounced.get());
+	assert!(swr.cache().get(&Key::Page(1)).is_none());
+	assert_eq!(fetcher.fetch_count(), 0);
+	hook.take_wants_redraw();
+
+	yield_now().await;
+	advance(Duration::from_millis(100)).await;
+	yield_now().await;
+	assert!(hook.take_wants_redraw());
+	let res = hook.within(|| debounced.get());
+	assert!(res.loading);
+	assert_eq!(debounced.key(), Some(&Key::Page(2)));
+	yield_now().await;
+	assert_eq!(hook.within(|| debounced.get()).data.as_deref(), Some(&2));
+
+	// the settled key's data is kept until the next key settles
+	debounced.set_key(&Key::Page(3));
+	assert_eq!(hook.within(|| debounced.get()).data.as_deref(), Some(&2));
+	yield_now().await;
+	advance(Duration::from_millis(300)).await;
+	let res = hook.within(|| debounced.get());
+	assert!(res.is_previous_data());
+	assert_eq!(res.data.as_deref(), Some(&2));
+	assert_eq!(inspect_entry(&swr, Key::Page(2), |entry| entry.strong_count.load(Ordering::Acquire)), Some(0));
+	yield_now().await;
+	assert_eq!(hook.within(|| debounced.get()).data.as_deref(), Some(&3));
+	assert_eq!(fetcher.fetch_count(), 2);
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct AddCommand {
+	key: Key,
+	amount: usize,
+	// the key fetched to "send" the command, so sending can be made to fail
+	send_key: Key
+}
+
+impl crate::Command<Fetcher> for AddCommand {
+	type Data = usize;
+
+	fn key(&self) -> Key {
+		self.key
+	}
+
+	fn apply(&self, data: Option<&usize>) -> Option<usize> {
+		data.map(|n| n + self.amount)
+	}
+
+	async fn send(&self, fetcher: &Fetcher) -> Result<(), Error> {
+		use crate::Fetcher as _;
+
+		fetcher.fetch::<usize>(&self.send_key).await.map(drop)
+	}
+}
+
+#[tokio::test(start_paused = true)]
+async fn mutation_queue() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+	let key = Key::Page(1);
+
+	let queue = swr.mutation_queue::<AddCommand>(Duration::from_secs(3));
+	queue.set_online(false);
+
+	hook.within(|| swr.get::<usize, _>(&key));
+	yield_now().await;
+
+	queue.push(AddCommand {
+		key,
+		amount: 10,
+		send_key: Key::Basic
+	});
+	queue.push(AddCommand {
+		key,
+		amount: 100,
+		send_key: Key::Basic
+	});
+	assert_eq!(queue.pending(&key).len(), 2);
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data.as_deref(), Some(&111));
+	assert_eq!(inspect_entry(&swr, key, |entry| entry.strong_count.load(Ordering::Acquire)), Some(1));
+
+	// fresh data is rebased onto the pending commands
+	swr.revalidate(&key);
+	hook.within(|| swr.get::<usize, _>(&key));
+	yield_now().await;
+	assert_eq!(fetcher.fetch_count(), 2);
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data.as_deref(), Some(&111));
+
+	queue.set_online(true);
+	yield_now().await;
+	yield_now().await;
+	assert!(queue.is_empty());
+	assert_eq!(fetcher.fetch_count(), 4);
+	assert_eq!(inspect_entry(&swr, key, |entry| entry.strong_count.load(Ordering::Acquire)), Some(0));
+
+	// once sent, the key is revalidated, and its data is no longer rebased
+	hook.within(|| swr.get::<usize, _>(&key));
+	yield_now().await;
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data.as_deref(), Some(&1));
+
+	// failed sends are retried
+	queue.push(AddCommand {
+		key,
+		amount: 10,
+		send_key: Key::ErrorNTimes(1)
+	});
+	yield_now().await;
+	assert_eq!(queue.len(), 1);
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data.as_deref(), Some(&11));
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	assert!(queue.is_empty());
+
+	// a queue which is still retrying doesn't keep itself or the cache alive
+	queue.push(AddCommand {
+		key,
+		amount: 10,
+		send_key: Key::AlwaysError
+	});
+	yield_now().await;
+	assert_eq!(queue.len(), 1);
+	drop(queue);
+	yield_now().await;
+	assert_eq!(Arc::strong_count(&swr.inner), 1);
+	assert_eq!(inspect_entry(&swr, key, |entry| entry.strong_count.load(Ordering::Acquire)), Some(0));
+}
+
+#[cfg(feature = "persist")]
+#[tokio::test(start_paused = true)]
+async fn mutation_queue_persist() {
+	let storage = MemoryStorage::<crate::persist::StoredCommand>::default();
+	let key = Key::Page(1);
+
+	{
+		let swr = SWR::new_in(Fetcher::new(), Tokio, MockHook::default());
+		let queue = swr.mutation_queue::<AddCommand>(Duration::from_secs(3));
+		queue.set_online(false);
+		queue.push(AddCommand {
+			key,
+			amount: 10,
+			send_key: Key::Basic
+		});
+		assert_eq!(queue.persist(&storage).unwrap(), 1);
+		// dropping the queue releases the keys of its pending commands
+		drop(queue);
+		assert_eq!(inspect_entry(&swr, key, |entry| entry.strong_count.load(Ordering::Acquire)), Some(0));
+		assert!(inspect_entry(&swr, key, |entry| entry.rebase.is_none()).unwrap());
+	}
+
+	let swr = SWR::new_in(Fetcher::new(), Tokio, MockHook::default());
+	swr.mutate(&key, Arc::new(1usize));
+	let queue = swr.mutation_queue::<AddCommand>(Duration::from_secs(3));
+	queue.set_online(false);
+	assert_eq!(queue.restore(&storage).unwrap(), 1);
+	assert_eq!(queue.pending(&key).len(), 1);
+	assert_eq!(swr.get_shallow::<usize, _>(&key).unwrap().data.as_deref(), Some(&11));
+}
+
+#[tokio::test(start_paused = true)]
+async fn transaction() {
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	hook.within(|| {
+		swr.get::<usize, _>(&Key::Page(1));
+		swr.get::<usize, _>(&Key::Page(2));
+	});
+	yield_now().await;
+	hook.take_wants_redraw();
+
+	let data = |key| swr.get_shallow::<usize, _>(&key).unwrap().data.map(|n| *n);
+
+	let task = swr.transaction(
+		|tx| {
+			tx.optimistic::<usize, _>(&Key::Page(1), Arc::new(10));
+			tx.optimistic::<usize, _>(&Key::Page(2), Arc::new(20));
+			tx.optimistic::<usize, _>(&Key::Page(1), Arc::new(11));
+			tx.optimistic::<usize, _>(&Key::Basic, Arc::new(30));
+		},
+		|_| async move { Err::<(), _>(Error) }
+	);
+	assert_eq!((data(Key::Page(1)), data(Key::Page(2)), data(Key::Basic)), (Some(11), Some(20), Some(30)));
+	assert!(hook.take_wants_redraw());
+
+	assert!(task.await.unwrap().is_err());
+	assert!(hook.take_wants_redraw());
+	// every key is restored to its data from before the transaction, even if it was staged twice
+	assert_eq!((data(Key::Page(1)), data(Key::Page(2)), data(Key::Basic)), (Some(1), Some(2), None));
+
+	swr.transaction(
+		|tx| {
+			tx.optimistic::<usize, _>(&Key::Page(1), Arc::new(10))
+				.optimistic::<usize, _>(&Key::Page(2), Arc::new(20));
+		},
+		|_| async move { Ok::<_, Error>(()) }
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!((data(Key::Page(1)), data(Key::Page(2))), (Some(10), Some(20)));
+
+	// keys whose data was replaced while the transaction ran keep their newer data
+	let task = swr.transaction(
+		|tx| {
+			tx.optimistic::<usize, _>(&Key::Page(1), Arc::new(100))
+				.optimistic::<usize, _>(&Key::Page(2), Arc::new(200));
+		},
+		|_| async move {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+			Err::<(), _>(Error)
+		}
+	);
+	swr.mutate(&Key::Page(1), Arc::new(101usize));
+	advance(Duration::from_secs(1)).await;
+	assert!(task.await.unwrap().is_err());
+	assert_eq!((data(Key::Page(1)), data(Key::Page(2))), (Some(101), Some(20)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn mutation() {
+	use crate::TriggerPolicy;
+
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+
+	// takes `n` seconds to resolve to `n`, failing if `n` is 0
+	let mutation = swr.mutation::<usize, usize, _, _, _, _, _>(&Key::Basic, |n, _, _| async move {
+		tokio::time::sleep(Duration::from_secs(n as u64)).await;
+		if n == 0 { Err(Error) } else { Ok(Arc::new(n)) }
+	});
+
+	assert!(mutation.trigger(1));
+	assert!(mutation.is_mutating());
+	assert!(hook.take_wants_redraw());
+	// ignored by the default policy
+	assert!(!mutation.trigger(2));
+
+	yield_now().await;
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert!(!mutation.is_mutating());
+	assert!(hook.take_wants_redraw());
+	assert_eq!(mutation.data().as_deref(), Some(&Arc::new(1)));
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.as_deref(), Some(&1));
+
+	assert!(mutation.trigger(0));
+	yield_now().await;
+	yield_now().await;
+	assert!(mutation.error().is_some());
+	assert_eq!(mutation.data().as_deref(), Some(&Arc::new(1)));
+	mutation.reset();
+	assert!(mutation.error().is_none() && mutation.data().is_none());
+
+	// only the most recent trigger's outcome is kept
+	let mutation = mutation.with_policy(TriggerPolicy::Latest);
+	assert!(mutation.trigger(3));
+	assert!(mutation.trigger(2));
+	yield_now().await;
+	advance(Duration::from_secs(2)).await;
+	yield_now().await;
+	assert!(mutation.is_mutating());
+	assert_eq!(mutation.data().as_deref(), Some(&Arc::new(2)));
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.as_deref(), Some(&2));
+	advance(Duration::from_secs(1)).await;
+	yield_now().await;
+	assert!(!mutation.is_mutating());
+	assert_eq!(mutation.data().as_deref(), Some(&Arc::new(2)));
+	// the earlier trigger's result doesn't overwrite the cache either
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.as_deref(), Some(&2));
+
+	// state is shared by all mutations of the key
+	let other = swr.mutation::<usize, usize, _, _, _, _, _>(&Key::Basic, |n, _, _| async move { Ok::<_, Error>(Arc::new(n)) });
+	assert_eq!(other.data().as_deref(), Some(&Arc::new(2)));
+	assert_eq!(inspect_entry(&swr, Key::Basic, |entry| entry.strong_count.load(Ordering::Acquire)), Some(2));
+	drop(other);
+	drop(mutation);
+	assert_eq!(inspect_entry(&swr, Key::Basic, |entry| entry.strong_count.load(Ordering::Acquire)), Some(0));
+}
+
+#[tokio::test(start_paused = true)]
+async fn latest_mutation_rollback() {
+	use crate::TriggerPolicy;
+
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+	let data = || swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.map(|data| *data);
+
+	// takes `n` seconds to resolve to `n`, failing if `n` is 0
+	let mutation = swr
+		.mutation::<usize, usize, _, _, _, _, _>(&Key::Basic, |n, _, _| async move {
+			tokio::time::sleep(Duration::from_secs(n as u64)).await;
+			if n == 0 { Err(Error) } else { Ok(Arc::new(n)) }
+		})
+		.with_policy(TriggerPolicy::Latest);
+	let optimistic = |data: usize| MutateOptions {
+		optimistic_data: Some(Arc::new(data)),
+		..MutateOptions::default()
+	};
+
+	swr.mutate(&Key::Basic, Arc::new(1usize));
+	assert!(mutation.trigger_with(3, optimistic(30)));
+	yield_now().await;
+	assert_eq!(data(), Some(30));
+
+	// the second trigger fails, rolling back past the optimistic data of the first, which is discarded
+	assert!(mutation.trigger_with(0, optimistic(20)));
+	yield_now().await;
+	yield_now().await;
+	assert!(mutation.error().is_some());
+	assert_eq!(data(), Some(1));
+
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	assert!(!mutation.is_mutating());
+	assert_eq!(data(), Some(1));
+}
+
+#[tokio::test(start_paused = true)]
+async fn history() {
+	let hook = MockHook::default();
+	let swr = SWR::new_in(Fetcher::new(), Tokio, hook.clone());
+	let data = || swr.get_shallow::<usize, _>(&Key::Basic).unwrap().data.map(|data| *data);
+
+	hook.within(|| {
+		let _ = swr.get_with::<usize, _>(
+			&Key::Basic,
+			Options {
+				history_depth: NonZeroU8::new(2),
+				..Options::immutable()
+			}
+		);
+	});
+	yield_now().await;
+	assert_eq!(data(), Some(42));
+
+	swr.mutate(&Key::Basic, Arc::new(1usize));
+	swr.mutate(&Key::Basic, Arc::new(2usize));
+	swr.mutate(&Key::Basic, Arc::new(3usize));
+	assert!(swr.undo(&Key::Basic));
+	assert_eq!(data(), Some(2));
+	assert!(swr.undo(&Key::Basic));
+	assert_eq!(data(), Some(1));
+	// 42 fell off the end of the history
+	assert!(!swr.can_undo(&Key::Basic) && !swr.undo(&Key::Basic));
+
+	assert!(swr.redo(&Key::Basic));
+	assert_eq!(data(), Some(2));
+	assert!(swr.can_redo(&Key::Basic));
+	// a new mutation discards what could be redone
+	swr.mutate(&Key::Basic, Arc::new(4usize));
+	assert!(!swr.can_redo(&Key::Basic));
+
+	// failed mutations which were rolled back aren't recorded
+	let res = swr
+		.mutate_with(
+			&Key::Basic,
+			MutateOptions {
+				optimistic_data: Some(Arc::new(5usize)),
+				..MutateOptions::default()
+			},
+			|_, _| async move { Err::<Arc<usize>, _>(Error) }
+		)
+		.await
+		.unwrap();
+	assert!(res.is_err());
+	assert_eq!(data(), Some(4));
+	assert!(swr.undo(&Key::Basic));
+	assert_eq!(data(), Some(2));
+
+	// a failed compensating mutator reverts the undo
+	let res = swr
+		.undo_with::<usize, _, _, _, _, _>(&Key::Basic, MutateOptions::default(), |restored, _| {
+			assert_eq!(restored.map(|data| **data), Some(1));
+			async move { Err::<Arc<usize>, _>(Error) }
+		})
+		.unwrap()
+		.await
+		.unwrap();
+	assert!(res.is_err());
+	assert_eq!(data(), Some(2));
+
+	let res = swr
+		.undo_with::<usize, _, _, _, _, _>(&Key::Basic, MutateOptions::default(), |restored, _| {
+			let restored = Arc::clone(restored.unwrap());
+			async move { Ok::<_, Error>(restored) }
+		})
+		.unwrap()
+		.await
+		.unwrap();
+	assert!(res.is_ok());
+	assert_eq!(data(), Some(1));
+	// the compensating mutation isn't recorded itself
+	assert!(!swr.can_undo(&Key::Basic) && swr.can_redo(&Key::Basic));
+
+	// a failed compensating mutator doesn't revert the undo if the data was replaced in the meantime
+	swr.mutate(&Key::Basic, Arc::new(6usize));
+	let task = swr
+		.undo_with::<usize, _, _, _, _, _>(&Key::Basic, MutateOptions::default(), |_, _| async move {
+			tokio::time::sleep(Duration::from_secs(1)).await;
+			Err::<Arc<usize>, _>(Error)
+		})
+		.unwrap();
+	assert_eq!(data(), Some(1));
+	let res = swr
+		.mutate_with(
+			&Key::Basic,
+			MutateOptions {
+				record_history: false,
+				..MutateOptions::default()
+			},
+			|_, _| async move { Ok::<_, Error>(Arc::new(7usize)) }
+		)
+		.await
+		.unwrap();
+	assert!(res.is_ok());
+	advance(Duration::from_secs(1)).await;
+	assert!(task.await.unwrap().is_err());
+	assert_eq!(data(), Some(7));
+
+	// fetched data discards the history
+	assert!(swr.can_redo(&Key::Basic));
+	swr.revalidate(&Key::Basic);
+	hook.within(|| {
+		let _ = swr.get::<usize, _>(&Key::Basic);
+	});
+	yield_now().await;
+	assert_eq!(data(), Some(42));
+	assert!(!swr.can_undo(&Key::Basic) && !swr.can_redo(&Key::Basic));
+
+	// bulk mutations & queued commands are recorded too
+	swr.mutate_where::<usize, _, _>(|key| *key == Key::Basic, |_, data| Arc::new(data.map_or(0, |data| **data) + 1));
+	let queue = swr.mutation_queue::<AddCommand>(Duration::from_secs(3));
+	queue.set_online(false);
+	queue.push(AddCommand {
+		key: Key::Basic,
+		amount: 10,
+		send_key: Key::Basic
+	});
+	assert_eq!(data(), Some(53));
+	assert!(swr.undo(&Key::Basic));
+	assert_eq!(data(), Some(43));
+	assert!(swr.undo(&Key::Basic));
+	assert_eq!(data(), Some(42));
+
+	// history is opt-in
+	swr.mutate(&Key::Page(1), Arc::new(1usize));
+	swr.mutate(&Key::Page(1), Arc::new(2usize));
+	assert!(!swr.undo(&Key::Page(1)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn batching() {
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+	swr.batch_type::<usize>(Duration::from_millis(50));
+
+	let keys = [Key::Page(1), Key::Page(2), Key::ErrorNTimes(1)];
+	let options = || Options {
+		error_retry_interval: Some(Duration::from_secs(3)),
+		..Options::immutable()
+	};
+
+	hook.within(|| {
+		for key in &keys {
+			let res = swr.get_with::<usize, _>(key, options());
+			assert!(res.loading);
+		}
+	});
+	yield_now().await;
+	yield_now().await;
+
+	assert_eq!(fetcher.batch_count(), 1);
+	assert_eq!(fetcher.fetch_count(), 3);
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Page(2)).unwrap().data, Some(Arc::new(2)));
+	assert!(swr.get_shallow::<usize, _>(&Key::ErrorNTimes(1)).unwrap().error.is_some());
+
+	// the failed key should be retried on its own, outside of a frame
+	hook.within(|| {
+		for key in &keys {
+			let _ = swr.get_with::<usize, _>(key, options());
+		}
+	});
+	advance(Duration::from_secs(3)).await;
+	yield_now().await;
+	advance(Duration::from_millis(50)).await;
+	yield_now().await;
+
+	assert_eq!(fetcher.batch_count(), 2);
+	assert_eq!(fetcher.fetch_count(), 4);
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::ErrorNTimes(1)).unwrap().data, Some(Arc::new(42)));
+
+	// keys whose fetch is aborted before the batch is dispatched should be dropped from the batch
+	hook.within(|| {
+		for key in [Key::Page(3), Key::Page(4)] {
+			assert!(swr.get_with::<usize, _>(&key, options()).loading);
+		}
+		swr.invalidate_where(|key| *key == Key::Page(3));
+	});
+	yield_now().await;
+	yield_now().await;
+
+	assert_eq!(fetcher.batch_count(), 3);
+	assert_eq!(fetcher.fetch_count(), 5);
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Page(3)).unwrap().data, None);
+	assert_eq!(swr.get_shallow::<usize, _>(&Key::Page(4)).unwrap().data, Some(Arc::new(4)));
+}
+
+#[cfg(feature = "persist")]
+struct MemoryStorage<T = crate::persist::StoredEntry>(parking_lot::Mutex<Vec<T>>);
+
+#[cfg(feature = "persist")]
+impl<T> Default for MemoryStorage<T> {
+	fn default() -> Self {
+		Self(parking_lot::Mutex::default())
+	}
+}
+
+#[cfg(feature = "persist")]
+impl<T: Clone + Send> crate::persist::Storage<T> for MemoryStorage<T> {
+	type Error = Infallible;
+
+	fn load(&self) -> Result<Vec<T>, Self::Error> {
+		Ok(self.0.lock().clone())
+	}
+
+	fn store(&self, records: &[T]) -> Result<(), Self::Error> {
+		*self.0.lock() = records.to_vec();
+		Ok(())
+	}
+}
+
+#[cfg(feature = "persist")]
+#[tokio::test(start_paused = true)]
+async fn persist_and_hydrate() {
+	let storage = MemoryStorage::default();
+
+	{
+		let swr = SWR::new_in(Fetcher::new(), Tokio, MockHook::default());
+		swr.persist_type::<usize>("usize");
+		swr.mutate(&Key::Basic, Arc::new(47usize));
+		// not registered, so shouldn't be persisted
+		swr.mutate(&Key::AlwaysError, Arc::new(47u8));
+		assert_eq!(swr.persist(&storage).unwrap(), 1);
+	}
+
+	let hook = MockHook::default();
+	let fetcher = Fetcher::new();
+	let swr = SWR::new_in(fetcher.clone(), Tokio, hook.clone());
+	swr.persist_type::<usize>("usize");
+	assert_eq!(swr.hydrate(&storage).unwrap(), 1);
+
+	hook.within(|| {
+		let res = swr.get_with::<usize, _>(&Key::Basic, Options::immutable());
+		assert_eq!(res.data, Some(Arc::new(47)));
+		assert!(!res.loading);
+		assert!(res.validating);
+	});
+
+	yield_now().await;
+
+	assert_eq!(fetcher.fetch_count(), 1);
+	inspect_entry(&swr, Key::Basic, |entry| {
+		assert_eq!(entry.data().unwrap().unwrap(), Arc::new(42usize));
+	})
+	.unwrap();
 }
@@ -0,0 +1,54 @@
+use std::{borrow::Borrow, hash::Hash, sync::Arc};
+
+use crate::{
+	SWRInner,
+	cache::{CacheEntryData, CacheSlot},
+	fetcher::Fetcher,
+	runtime::Runtime
+};
+
+/// A set of optimistic updates to multiple keys which are committed or rolled back together, staged in the closure
+/// passed to [`SWR::transaction`][crate::SWR::transaction].
+pub struct Transaction<'s, F: Fetcher, R: Runtime> {
+	pub(crate) staged: Vec<(CacheSlot, CacheEntryData)>,
+	pub(crate) revalidate: bool,
+	inner: &'s SWRInner<F, R>
+}
+
+impl<'s, F: Fetcher, R: Runtime> Transaction<'s, F, R> {
+	pub(crate) fn new(inner: &'s SWRInner<F, R>) -> Self {
+		Self {
+			staged: Vec::new(),
+			revalidate: false,
+			inner
+		}
+	}
+
+	/// Stages `data` to replace the key's data for the duration of the transaction, creating the key's cache entry if
+	/// it doesn't exist.
+	///
+	/// If the transaction's mutator fails, the key is restored to the data it had before the transaction.
+	pub fn optimistic<T, K>(&mut self, key: &K, data: Arc<F::Response<T>>) -> &mut Self
+	where
+		T: Send + Sync + 'static,
+		K: Hash + Eq + ?Sized,
+		F::Key: Borrow<K> + for<'k> From<&'k K>
+	{
+		let slot = self.inner.cache.get_or_create(key);
+		self.staged.push((
+			slot,
+			CacheEntryData {
+				value: data,
+				#[cfg(debug_assertions)]
+				type_name: std::any::type_name::<T>()
+			}
+		));
+		self
+	}
+
+	/// Revalidates every key touched by the transaction once the mutator succeeds.
+	pub fn revalidate(&mut self) -> &mut Self {
+		self.revalidate = true;
+		self
+	}
+}
@@ -1,4 +1,13 @@
-use std::{future::Future, sync::atomic::Ordering, time::Duration};
+use std::{
+	collections::hash_map::RandomState,
+	future::{Future, poll_fn},
+	hash::{BuildHasher, Hasher},
+	sync::atomic::Ordering,
+	task::{Poll, Waker},
+	time::Duration
+};
+
+use parking_lot::Mutex;
 
 // Use `tokio`'s `Instant` wrapper in testing since we can 'advance' time with `tokio::time::advance`
 #[cfg(test)]
@@ -6,11 +15,16 @@ pub type Instant = tokio::time::Instant;
 #[cfg(not(test))]
 pub type Instant = std::time::Instant;
 
-use crate::runtime::{Runtime, Task};
+use crate::{
+	fetcher::CancellationToken,
+	runtime::{Runtime, Task}
+};
 
 pub struct TaskSlot<R: Runtime> {
 	runtime: R,
-	task: Option<R::Task<()>>
+	task: Option<R::Task<()>>,
+	// cancelled when the current task is aborted
+	cancellation: Option<CancellationToken>
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -25,10 +39,22 @@ pub enum TaskStartMode {
 
 impl<R: Runtime> TaskSlot<R> {
 	pub fn new(runtime: R) -> Self {
-		Self { runtime, task: None }
+		Self {
+			runtime,
+			task: None,
+			cancellation: None
+		}
 	}
 
 	pub fn insert<F>(&mut self, mode: TaskStartMode, fut: F) -> bool
+	where
+		F: Future<Output = ()> + Send + 'static
+	{
+		self.insert_cancellable(mode, None, fut)
+	}
+
+	/// Like [`TaskSlot::insert`], but `cancellation` is cancelled if the task is later aborted.
+	pub fn insert_cancellable<F>(&mut self, mode: TaskStartMode, cancellation: Option<CancellationToken>, fut: F) -> bool
 	where
 		F: Future<Output = ()> + Send + 'static
 	{
@@ -40,15 +66,16 @@ impl<R: Runtime> TaskSlot<R> {
 					}
 				}
 			}
-			TaskStartMode::Abort => {
+			TaskStartMode::Abort => self.abort(),
+			TaskStartMode::Override => {
 				if let Some(handle) = self.task.take() {
-					handle.abort();
+					handle.detach();
 				}
 			}
-			TaskStartMode::Override => {}
 		}
 
-		self.task.replace(self.runtime.spawn(fut));
+		self.task = Some(self.runtime.spawn(fut));
+		self.cancellation = cancellation;
 		true
 	}
 
@@ -63,9 +90,53 @@ impl<R: Runtime> TaskSlot<R> {
 		if let Some(handle) = self.task.take() {
 			handle.abort();
 		}
+		if let Some(cancellation) = self.cancellation.take() {
+			cancellation.cancel();
+		}
 	}
 }
 
+/// A single-use channel for sending a value from one task to another.
+pub struct Oneshot<T> {
+	// outer `Option` is whether or not the channel has been completed; `None` inside means the sender gave up
+	state: Mutex<(Option<Option<T>>, Option<Waker>)>
+}
+
+impl<T> Default for Oneshot<T> {
+	fn default() -> Self {
+		Self { state: Mutex::new((None, None)) }
+	}
+}
+
+impl<T> Oneshot<T> {
+	pub fn send(&self, value: Option<T>) {
+		let mut state = self.state.lock();
+		state.0 = Some(value);
+		if let Some(waker) = state.1.take() {
+			waker.wake();
+		}
+	}
+
+	pub async fn recv(&self) -> Option<T> {
+		poll_fn(|cx| {
+			let mut state = self.state.lock();
+			if let Some(value) = state.0.take() {
+				return Poll::Ready(value);
+			}
+			state.1 = Some(cx.waker().clone());
+			Poll::Pending
+		})
+		.await
+	}
+}
+
+/// Returns a random number. Not suitable for anything but jitter.
+pub fn random_u64() -> u64 {
+	// `RandomState` is seeded randomly, and each new instance increments its seed, so this produces a different value
+	// each call.
+	RandomState::new().build_hasher().finish()
+}
+
 /// Returns `true` if the time elapsed since `prev_time` exceeds the `throttle_time`.
 pub fn throttle(prev_time: Option<Instant>, throttle_time: Option<Duration>) -> bool {
 	match (prev_time, throttle_time) {
@@ -116,7 +187,7 @@ mod tests {
 	use tokio::task::yield_now;
 
 	use super::{TaskSlot, TaskStartMode};
-	use crate::runtime::Tokio;
+	use crate::{fetcher::CancellationToken, runtime::Tokio};
 
 	#[tokio::test]
 	async fn task_start_soft() {
@@ -171,4 +242,17 @@ mod tests {
 		yield_now().await;
 		assert!(!finished.load(Ordering::Acquire));
 	}
+
+	#[tokio::test]
+	async fn task_abort_cancels() {
+		let cancellation = CancellationToken::default();
+
+		let mut slot = TaskSlot::new(Tokio);
+		slot.insert_cancellable(TaskStartMode::Soft, Some(cancellation.clone()), std::future::pending());
+		assert!(!cancellation.is_cancelled());
+
+		assert!(slot.insert(TaskStartMode::Abort, async {}));
+		assert!(cancellation.is_cancelled());
+		cancellation.cancelled().await;
+	}
 }